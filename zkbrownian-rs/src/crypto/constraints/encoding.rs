@@ -0,0 +1,271 @@
+//! Point encoding gadgets
+//!
+//! In-circuit counterpart of [`crate::crypto::encoding`]. The BLS12-381 base
+//! field is not native to the circuit, so the gadget does not check that the
+//! limbs describe a point on the curve; it only enforces the shape of the
+//! encoding (limb widths and flag values). The point itself is expected to be
+//! public, e.g. φ_ν as published in the message, so the verifier encodes it
+//! natively and feeds the limbs in as public inputs. Hidden G2 keys are
+//! instead pinned down by a Merkle leaf of the registered key tree.
+
+use crate::crypto::encoding::{
+    encode_g1, encode_g2, FQ_LIMB_BITS, FQ_NUM_LIMBS, FQ_TOP_LIMB_BITS, G1_ENCODING_LEN,
+    G2_ENCODING_LEN,
+};
+use crate::types::{G1Point, G2Point, ScalarField};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    boolean::Boolean,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+    R1CSVar,
+};
+use ark_relations::gr1cs::{Namespace, SynthesisError};
+use ark_std::borrow::Borrow;
+
+/// Enforce that `value` fits into `num_bits` bits
+///
+/// Allocates the little-endian bit decomposition of `value` as witnesses and
+/// constrains it to recompose to `value`. Returns the bits.
+pub fn enforce_bit_length(
+    value: &FpVar<ScalarField>,
+    num_bits: usize,
+) -> Result<Vec<Boolean<ScalarField>>, SynthesisError> {
+    let cs = value.cs();
+    let native_bits = value.value().ok().map(|v| v.into_bigint().to_bits_le());
+
+    let bits = (0..num_bits)
+        .map(|i| {
+            Boolean::new_witness(cs.clone(), || {
+                native_bits
+                    .as_ref()
+                    .map(|bits| bits[i])
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut recomposed = FpVar::zero();
+    let mut coeff = ScalarField::from(1u64);
+    for bit in &bits {
+        recomposed += FpVar::from(bit.clone()) * coeff;
+        coeff = coeff + coeff;
+    }
+    recomposed.enforce_equal(value)?;
+
+    Ok(bits)
+}
+
+/// Allocated encoding of a G1 point
+#[derive(Clone, Debug)]
+pub struct G1EncodingVar {
+    /// Little-endian 128-bit limbs of the x-coordinate
+    pub limbs: Vec<FpVar<ScalarField>>,
+    /// Flag element: sign + 2 · infinity
+    pub flags: FpVar<ScalarField>,
+}
+
+impl G1EncodingVar {
+    /// The encoding as a flat list, in the order used by [`encode_g1`]
+    pub fn to_field_elements(&self) -> Vec<FpVar<ScalarField>> {
+        let mut elements = self.limbs.clone();
+        elements.push(self.flags.clone());
+        elements
+    }
+}
+
+impl AllocVar<G1Point, ScalarField> for G1EncodingVar {
+    fn new_variable<T: Borrow<G1Point>>(
+        cs: impl Into<Namespace<ScalarField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let encoding = f().map(|p| encode_g1(p.borrow())).ok();
+
+        let elements = (0..G1_ENCODING_LEN)
+            .map(|i| {
+                FpVar::new_variable(
+                    cs.clone(),
+                    || encoding.map(|e| e[i]).ok_or(SynthesisError::AssignmentMissing),
+                    mode,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        enforce_encoding_shape(&elements[..FQ_NUM_LIMBS], &elements[FQ_NUM_LIMBS])?;
+
+        Ok(Self {
+            limbs: elements[..FQ_NUM_LIMBS].to_vec(),
+            flags: elements[FQ_NUM_LIMBS].clone(),
+        })
+    }
+}
+
+/// Allocated encoding of a G2 point
+#[derive(Clone, Debug)]
+pub struct G2EncodingVar {
+    /// Little-endian 128-bit limbs of x.c0, then of x.c1
+    pub limbs: Vec<FpVar<ScalarField>>,
+    /// Flag element: sign + 2 · infinity
+    pub flags: FpVar<ScalarField>,
+}
+
+impl G2EncodingVar {
+    /// The encoding as a flat list, in the order used by [`encode_g2`]
+    pub fn to_field_elements(&self) -> Vec<FpVar<ScalarField>> {
+        let mut elements = self.limbs.clone();
+        elements.push(self.flags.clone());
+        elements
+    }
+}
+
+impl AllocVar<G2Point, ScalarField> for G2EncodingVar {
+    fn new_variable<T: Borrow<G2Point>>(
+        cs: impl Into<Namespace<ScalarField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let encoding = f().map(|p| encode_g2(p.borrow())).ok();
+
+        let elements = (0..G2_ENCODING_LEN)
+            .map(|i| {
+                FpVar::new_variable(
+                    cs.clone(),
+                    || encoding.map(|e| e[i]).ok_or(SynthesisError::AssignmentMissing),
+                    mode,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Self::from_field_elements(&elements)
+    }
+}
+
+impl G2EncodingVar {
+    /// Constrain already allocated elements to the shape of an encoding
+    ///
+    /// Lets a circuit allocate the encoding next to other values, e.g. as
+    /// the committed witnesses of a commit-carrying proof, before the shape
+    /// checks allocate their bits.
+    pub fn from_field_elements(elements: &[FpVar<ScalarField>]) -> Result<Self, SynthesisError> {
+        if elements.len() != G2_ENCODING_LEN {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+
+        let flags = 2 * FQ_NUM_LIMBS;
+        enforce_encoding_shape(&elements[..flags], &elements[flags])?;
+
+        Ok(Self {
+            limbs: elements[..flags].to_vec(),
+            flags: elements[flags].clone(),
+        })
+    }
+}
+
+/// Constrain the shape of a point encoding: limbs of one or more Fq
+/// elements followed by the flag element
+fn enforce_encoding_shape(
+    limbs: &[FpVar<ScalarField>],
+    flags: &FpVar<ScalarField>,
+) -> Result<(), SynthesisError> {
+    // Limb widths make the limb decomposition of each coordinate unique
+    for (i, limb) in limbs.iter().enumerate() {
+        let width = if i % FQ_NUM_LIMBS == FQ_NUM_LIMBS - 1 {
+            FQ_TOP_LIMB_BITS
+        } else {
+            FQ_LIMB_BITS
+        };
+        enforce_bit_length(limb, width)?;
+    }
+
+    // flags ∈ {0, 1, 2}: two bits that are never both set, and an
+    // infinity flag forces the x-coordinate limbs to zero
+    let flag_bits = enforce_bit_length(flags, 2)?;
+    let (sign, infinity) = (&flag_bits[0], &flag_bits[1]);
+    (FpVar::from(sign.clone()) * FpVar::from(infinity.clone())).enforce_equal(&FpVar::zero())?;
+    for limb in limbs {
+        limb.conditional_enforce_equal(&FpVar::zero(), infinity)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::constraints::PoseidonHashGadget;
+    use crate::crypto::PoseidonHash;
+    use ark_bls12_381::{G1Projective, G2Projective};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_encoding_var_matches_native() {
+        let mut rng = thread_rng();
+
+        for point in [G1Projective::rand(&mut rng).into_affine(), G1Point::zero()] {
+            let cs = ConstraintSystem::<ScalarField>::new_ref();
+            let encoding_var = G1EncodingVar::new_input(cs.clone(), || Ok(point)).unwrap();
+
+            assert_eq!(
+                encoding_var.to_field_elements().value().unwrap(),
+                encode_g1(&point).to_vec()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        for point in [G2Projective::rand(&mut rng).into_affine(), G2Point::zero()] {
+            let cs = ConstraintSystem::<ScalarField>::new_ref();
+            let encoding_var = G2EncodingVar::new_witness(cs.clone(), || Ok(point)).unwrap();
+
+            assert_eq!(
+                encoding_var.to_field_elements().value().unwrap(),
+                encode_g2(&point).to_vec()
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_theta_chain_gadget() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+
+        let phi = G1Projective::rand(&mut rng).into_affine();
+        let (sid, pid, nu) = (1000u64, 42u32, 2usize);
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let phi_var = G1EncodingVar::new_input(cs.clone(), || Ok(phi)).unwrap();
+        let sid_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(sid))).unwrap();
+        let pid_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(pid as u64))).unwrap();
+        let nu_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(nu as u64))).unwrap();
+
+        let theta_var = gadget.hash_theta(&phi_var, &sid_var, &pid_var, &nu_var).unwrap();
+
+        assert_eq!(
+            theta_var.value().unwrap(),
+            hasher.hash_theta(&encode_g1(&phi), sid, pid, nu)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_bit_length_rejects_overflow() {
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let value = FpVar::new_witness(cs.clone(), || Ok(ScalarField::from(1u64 << 8))).unwrap();
+        enforce_bit_length(&value, 8).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let value = FpVar::new_witness(cs.clone(), || Ok(ScalarField::from(255u64))).unwrap();
+        enforce_bit_length(&value, 8).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
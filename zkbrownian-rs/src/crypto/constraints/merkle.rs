@@ -0,0 +1,202 @@
+//! Merkle tree gadgets
+//!
+//! In-circuit counterpart of [`crate::crypto::merkle`]. The path position is
+//! allocated as one Boolean per level, so the leaf index stays hidden unless
+//! the circuit chooses to expose it.
+
+use crate::crypto::constraints::encoding::G2EncodingVar;
+use crate::crypto::constraints::poseidon::PoseidonHashGadget;
+use crate::crypto::merkle::MerklePath;
+use crate::crypto::poseidon::DomainTag;
+use crate::types::ScalarField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    boolean::Boolean,
+    fields::{fp::FpVar, FieldVar},
+};
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+
+/// Leaf for an encoded public key and its metadata digest
+///
+/// Mirrors [`crate::crypto::merkle::merkle_leaf`].
+pub fn merkle_leaf_gadget(
+    gadget: &PoseidonHashGadget,
+    pk: &G2EncodingVar,
+    md: &FpVar<ScalarField>,
+) -> Result<FpVar<ScalarField>, SynthesisError> {
+    let mut inputs = pk.to_field_elements();
+    inputs.push(md.clone());
+    gadget.hash_with_domain(DomainTag::MerkleLeaf, &inputs)
+}
+
+/// Neighbour tree leaf for an encoded public key and its bucket bound
+///
+/// Mirrors [`crate::crypto::merkle::neighbour_leaf`].
+pub fn neighbour_leaf_gadget(
+    gadget: &PoseidonHashGadget,
+    pk: &G2EncodingVar,
+    bound: &FpVar<ScalarField>,
+) -> Result<FpVar<ScalarField>, SynthesisError> {
+    let mut inputs = pk.to_field_elements();
+    inputs.push(bound.clone());
+    gadget.hash_with_domain(DomainTag::NeighbourLeaf, &inputs)
+}
+
+/// Inner node: Hash(left, right)
+///
+/// Mirrors [`crate::crypto::merkle::merkle_node`].
+pub fn merkle_node_gadget(
+    gadget: &PoseidonHashGadget,
+    left: &FpVar<ScalarField>,
+    right: &FpVar<ScalarField>,
+) -> Result<FpVar<ScalarField>, SynthesisError> {
+    gadget.hash_with_domain(DomainTag::MerkleNode, &[left.clone(), right.clone()])
+}
+
+/// Hiding commitment to a leaf: Hash(leaf, r)
+///
+/// Mirrors [`crate::crypto::merkle::commit_leaf`].
+pub fn commit_leaf_gadget(
+    gadget: &PoseidonHashGadget,
+    leaf: &FpVar<ScalarField>,
+    r: &FpVar<ScalarField>,
+) -> Result<FpVar<ScalarField>, SynthesisError> {
+    gadget.hash_with_domain(DomainTag::LeafCommitment, &[leaf.clone(), r.clone()])
+}
+
+/// Allocated authentication path
+#[derive(Clone, Debug)]
+pub struct MerklePathVar {
+    /// Bit j is set if the node at level j is a right child
+    pub index_bits: Vec<Boolean<ScalarField>>,
+    /// Siblings from the leaf level up
+    pub siblings: Vec<FpVar<ScalarField>>,
+}
+
+impl MerklePathVar {
+    /// Allocate a path of `depth` levels as witnesses
+    ///
+    /// The shape only depends on `depth`, so the same constraints are
+    /// generated with and without an assignment (e.g. during setup).
+    pub fn new_witness(
+        cs: ConstraintSystemRef<ScalarField>,
+        depth: usize,
+        path: Option<&MerklePath>,
+    ) -> Result<Self, SynthesisError> {
+        let index_bits = (0..depth)
+            .map(|level| {
+                Boolean::new_witness(cs.clone(), || {
+                    path.map(|p| p.is_right(level))
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let siblings = (0..depth)
+            .map(|level| {
+                FpVar::new_witness(cs.clone(), || {
+                    path.and_then(|p| p.siblings.get(level).copied())
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            index_bits,
+            siblings,
+        })
+    }
+
+    /// The leaf index recomposed from the position bits
+    pub fn index(&self) -> FpVar<ScalarField> {
+        let mut index = FpVar::zero();
+        let mut coeff = ScalarField::from(1u64);
+        for bit in &self.index_bits {
+            index += FpVar::from(bit.clone()) * coeff;
+            coeff = coeff + coeff;
+        }
+        index
+    }
+
+    /// Hash `leaf` up to the root along the path
+    ///
+    /// Mirrors [`MerklePath::compute_root`].
+    pub fn compute_root(
+        &self,
+        gadget: &PoseidonHashGadget,
+        leaf: &FpVar<ScalarField>,
+    ) -> Result<FpVar<ScalarField>, SynthesisError> {
+        let mut node = leaf.clone();
+        for (is_right, sibling) in self.index_bits.iter().zip(&self.siblings) {
+            let left = is_right.select(sibling, &node)?;
+            let right = is_right.select(&node, sibling)?;
+            node = merkle_node_gadget(gadget, &left, &right)?;
+        }
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::merkle::test_utils::key_tree;
+    use crate::crypto::merkle::{merkle_leaf, MERKLE_DEPTH};
+    use ark_r1cs_std::{eq::EqGadget, R1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_path_gadget_matches_native() {
+        let (hasher, pks, metadata, tree) = key_tree(5);
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+
+        let k = 3;
+        let path = tree.path(k).unwrap();
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let pk = G2EncodingVar::new_witness(cs.clone(), || Ok(pks[k].pk)).unwrap();
+        let md = FpVar::new_witness(cs.clone(), || Ok(metadata[k])).unwrap();
+        let path_var = MerklePathVar::new_witness(cs.clone(), MERKLE_DEPTH, Some(&path)).unwrap();
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+
+        let leaf = merkle_leaf_gadget(&gadget, &pk, &md).unwrap();
+        assert_eq!(
+            leaf.value().unwrap(),
+            merkle_leaf(&hasher, &pks[k], &metadata[k])
+        );
+        assert_eq!(
+            path_var.index().value().unwrap(),
+            ScalarField::from(k as u64)
+        );
+
+        path_var
+            .compute_root(&gadget, &leaf)
+            .unwrap()
+            .enforce_equal(&root)
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_path_gadget_rejects_wrong_leaf() {
+        let (hasher, pks, metadata, tree) = key_tree(4);
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+        let (_, outsider) = keygen(&mut thread_rng());
+
+        // An unregistered key with the path of leaf 1
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let pk = G2EncodingVar::new_witness(cs.clone(), || Ok(outsider.pk)).unwrap();
+        let md = FpVar::new_witness(cs.clone(), || Ok(metadata[1])).unwrap();
+        let path = tree.path(1).unwrap();
+        let path_var = MerklePathVar::new_witness(cs.clone(), MERKLE_DEPTH, Some(&path)).unwrap();
+        let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+
+        let leaf = merkle_leaf_gadget(&gadget, &pk, &md).unwrap();
+        path_var
+            .compute_root(&gadget, &leaf)
+            .unwrap()
+            .enforce_equal(&root)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
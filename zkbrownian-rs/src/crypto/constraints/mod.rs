@@ -0,0 +1,15 @@
+//! R1CS gadgets for the cryptographic primitives
+//!
+//! Every gadget in this module mirrors a native implementation in
+//! [`crate::crypto`] and shares its parameters, so values computed in-circuit
+//! match the values computed by the protocol outside the circuit.
+
+pub mod encoding;
+pub mod merkle;
+pub mod poseidon;
+pub mod prf;
+
+pub use encoding::*;
+pub use merkle::*;
+pub use poseidon::*;
+pub use prf::*;
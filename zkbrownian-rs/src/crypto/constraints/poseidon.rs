@@ -0,0 +1,386 @@
+//! Poseidon hash gadget
+//!
+//! In-circuit counterpart of [`PoseidonHash`]. The gadget is built from the
+//! same [`PoseidonConfig`] and walks the same round schedule and sponge
+//! layout, so `PoseidonHashGadget::hash` on allocated inputs always equals
+//! `PoseidonHash::hash` on their values.
+
+use crate::crypto::constraints::encoding::G1EncodingVar;
+use crate::crypto::poseidon::{DomainTag, PoseidonConfig, PoseidonHash, SpongeMode};
+use crate::types::ScalarField;
+use ark_r1cs_std::fields::{fp::FpVar, FieldVar};
+use ark_relations::gr1cs::SynthesisError;
+
+/// Poseidon hasher over allocated BLS12-381 scalar field variables
+#[derive(Clone, Debug)]
+pub struct PoseidonHashGadget {
+    config: PoseidonConfig,
+}
+
+impl PoseidonHashGadget {
+    /// Create a gadget with the given Poseidon parameters
+    pub fn new(config: &PoseidonConfig) -> Self {
+        Self {
+            config: config.clone(),
+        }
+    }
+
+    /// Create a gadget using the same parameters as a native hasher
+    pub fn from_native(hasher: &PoseidonHash) -> Self {
+        Self::new(hasher.config())
+    }
+
+    /// Get the underlying Poseidon parameters
+    pub fn config(&self) -> &PoseidonConfig {
+        &self.config
+    }
+
+    /// Apply the Poseidon permutation to `state` in place
+    ///
+    /// Mirrors [`PoseidonConfig::permute`].
+    pub fn permute(&self, state: &mut [FpVar<ScalarField>]) -> Result<(), SynthesisError> {
+        assert_eq!(state.len(), self.config.width(), "Poseidon state has wrong width");
+
+        let half_full = self.config.full_rounds / 2;
+        let mut round = 0;
+
+        for _ in 0..half_full {
+            self.full_round(state, round)?;
+            round += 1;
+        }
+        for _ in 0..self.config.partial_rounds {
+            self.partial_round(state, round)?;
+            round += 1;
+        }
+        for _ in 0..(self.config.full_rounds - half_full) {
+            self.full_round(state, round)?;
+            round += 1;
+        }
+
+        Ok(())
+    }
+
+    fn full_round(
+        &self,
+        state: &mut [FpVar<ScalarField>],
+        round: usize,
+    ) -> Result<(), SynthesisError> {
+        self.apply_ark(state, round);
+        for elem in state.iter_mut() {
+            *elem = elem.pow_by_constant([self.config.alpha])?;
+        }
+        self.apply_mds(state);
+        Ok(())
+    }
+
+    fn partial_round(
+        &self,
+        state: &mut [FpVar<ScalarField>],
+        round: usize,
+    ) -> Result<(), SynthesisError> {
+        self.apply_ark(state, round);
+        state[0] = state[0].pow_by_constant([self.config.alpha])?;
+        self.apply_mds(state);
+        Ok(())
+    }
+
+    fn apply_ark(&self, state: &mut [FpVar<ScalarField>], round: usize) {
+        for (elem, c) in state.iter_mut().zip(self.config.ark[round].iter()) {
+            *elem += *c;
+        }
+    }
+
+    fn apply_mds(&self, state: &mut [FpVar<ScalarField>]) {
+        let new_state: Vec<FpVar<ScalarField>> = self
+            .config
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(FpVar::zero(), |acc, (m, s)| acc + s * *m)
+            })
+            .collect();
+        state.clone_from_slice(&new_state);
+    }
+
+    /// Start a sponge for the given domain
+    pub fn sponge(&self, tag: DomainTag) -> PoseidonSpongeVar<'_> {
+        PoseidonSpongeVar::new(self, tag)
+    }
+
+    /// Hash allocated field elements in the generic domain
+    ///
+    /// Mirrors [`PoseidonHash::hash`].
+    pub fn hash(&self, inputs: &[FpVar<ScalarField>]) -> Result<FpVar<ScalarField>, SynthesisError> {
+        self.hash_with_domain(DomainTag::Generic, inputs)
+    }
+
+    /// Hash allocated field elements in a specific domain
+    ///
+    /// Mirrors [`PoseidonHash::hash_with_domain`].
+    pub fn hash_with_domain(
+        &self,
+        tag: DomainTag,
+        inputs: &[FpVar<ScalarField>],
+    ) -> Result<FpVar<ScalarField>, SynthesisError> {
+        let mut sponge = self.sponge(tag);
+        sponge.absorb(inputs)?;
+        sponge.squeeze_one()
+    }
+
+    /// Hash theta derivation: θ = Hash(φ_ν, sid, pid, ν)
+    ///
+    /// Mirrors [`PoseidonHash::hash_theta`]; `sid`, `pid` and `nu` are
+    /// expected to be allocated as field elements by the caller.
+    pub fn hash_theta(
+        &self,
+        phi_prev: &G1EncodingVar,
+        sid: &FpVar<ScalarField>,
+        pid: &FpVar<ScalarField>,
+        nu: &FpVar<ScalarField>,
+    ) -> Result<FpVar<ScalarField>, SynthesisError> {
+        let mut inputs = phi_prev.to_field_elements();
+        inputs.extend([sid.clone(), pid.clone(), nu.clone()]);
+        self.hash_with_domain(DomainTag::Theta, &inputs)
+    }
+}
+
+/// Duplex sponge over allocated variables
+///
+/// Mirrors [`crate::crypto::poseidon::PoseidonSponge`]: the position and
+/// mode bookkeeping is independent of the witness, so the same sequence of
+/// `absorb`/`squeeze` calls yields the same outputs natively and in-circuit.
+#[derive(Clone, Debug)]
+pub struct PoseidonSpongeVar<'a> {
+    gadget: &'a PoseidonHashGadget,
+    state: Vec<FpVar<ScalarField>>,
+    position: usize,
+    mode: SpongeMode,
+}
+
+impl<'a> PoseidonSpongeVar<'a> {
+    /// Create a fresh sponge for the given domain
+    pub fn new(gadget: &'a PoseidonHashGadget, tag: DomainTag) -> Self {
+        let mut state = vec![FpVar::zero(); gadget.config.width()];
+        state[0] = FpVar::constant(tag.to_field());
+
+        Self {
+            gadget,
+            state,
+            position: 0,
+            mode: SpongeMode::Absorbing,
+        }
+    }
+
+    /// Absorb allocated field elements into the sponge
+    pub fn absorb(&mut self, inputs: &[FpVar<ScalarField>]) -> Result<(), SynthesisError> {
+        let gadget = self.gadget;
+        let config = &gadget.config;
+
+        if self.mode == SpongeMode::Squeezing {
+            self.mode = SpongeMode::Absorbing;
+            self.position = 0;
+        }
+
+        for input in inputs {
+            if self.position == config.rate {
+                gadget.permute(&mut self.state)?;
+                self.position = 0;
+            }
+            self.state[config.capacity + self.position] += input;
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    /// Squeeze `count` field elements out of the sponge
+    pub fn squeeze(&mut self, count: usize) -> Result<Vec<FpVar<ScalarField>>, SynthesisError> {
+        let gadget = self.gadget;
+        let config = &gadget.config;
+
+        if self.mode == SpongeMode::Absorbing {
+            if self.position == config.rate {
+                gadget.permute(&mut self.state)?;
+                self.position = 0;
+            }
+            self.state[config.capacity + self.position] += ScalarField::from(1u64);
+            gadget.permute(&mut self.state)?;
+            self.position = 0;
+            self.mode = SpongeMode::Squeezing;
+        }
+
+        let mut outputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            if self.position == config.rate {
+                gadget.permute(&mut self.state)?;
+                self.position = 0;
+            }
+            outputs.push(self.state[config.capacity + self.position].clone());
+            self.position += 1;
+        }
+        Ok(outputs)
+    }
+
+    /// Squeeze a single field element
+    pub fn squeeze_one(&mut self) -> Result<FpVar<ScalarField>, SynthesisError> {
+        Ok(self.squeeze(1)?.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_r1cs_std::{alloc::AllocVar, R1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_permutation_matches_native() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+
+        let mut native_state: Vec<ScalarField> = (0..hasher.config().width())
+            .map(|_| ScalarField::rand(&mut rng))
+            .collect();
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let mut state_var = native_state
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        hasher.config().permute(&mut native_state);
+        gadget.permute(&mut state_var).unwrap();
+
+        assert_eq!(state_var.value().unwrap(), native_state);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_hash_matches_native_on_random_inputs() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+
+        for len in 0..6 {
+            let inputs: Vec<ScalarField> = (0..len).map(|_| ScalarField::rand(&mut rng)).collect();
+
+            let cs = ConstraintSystem::<ScalarField>::new_ref();
+            let input_vars = inputs
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            let out_var = gadget.hash(&input_vars).unwrap();
+
+            assert_eq!(out_var.value().unwrap(), hasher.hash(&inputs), "length {}", len);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_hash_theta_matches_native() {
+        use crate::crypto::encoding::encode_g1;
+        use ark_bls12_381::G1Projective;
+        use ark_ec::CurveGroup;
+
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+
+        let phi_prev = G1Projective::rand(&mut rng).into_affine();
+        let (sid, pid, nu) = (1000u64, 42u32, 3usize);
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let phi_var = G1EncodingVar::new_witness(cs.clone(), || Ok(phi_prev)).unwrap();
+        let sid_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(sid))).unwrap();
+        let pid_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(pid as u64))).unwrap();
+        let nu_var = FpVar::new_input(cs.clone(), || Ok(ScalarField::from(nu as u64))).unwrap();
+
+        let theta_var = gadget.hash_theta(&phi_var, &sid_var, &pid_var, &nu_var).unwrap();
+
+        assert_eq!(
+            theta_var.value().unwrap(),
+            hasher.hash_theta(&encode_g1(&phi_prev), sid, pid, nu)
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_domain_hash_matches_native() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+        let inputs: Vec<ScalarField> = (0..3).map(|_| ScalarField::rand(&mut rng)).collect();
+
+        for tag in [DomainTag::MerkleNode, DomainTag::LeafCommitment] {
+            let cs = ConstraintSystem::<ScalarField>::new_ref();
+            let input_vars = inputs
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            let out_var = gadget.hash_with_domain(tag, &input_vars).unwrap();
+            assert_eq!(out_var.value().unwrap(), hasher.hash_with_domain(tag, &inputs));
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_sponge_matches_native() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+        let inputs: Vec<ScalarField> = (0..7).map(|_| ScalarField::rand(&mut rng)).collect();
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let input_vars = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        // Interleave absorbs and squeezes of uneven lengths
+        let mut native = hasher.sponge(DomainTag::Generic);
+        native.absorb(&inputs[..3]);
+        let mut native_out = native.squeeze(3);
+        native.absorb(&inputs[3..]);
+        native_out.extend(native.squeeze(2));
+
+        let mut sponge_var = gadget.sponge(DomainTag::Generic);
+        sponge_var.absorb(&input_vars[..3]).unwrap();
+        let mut out_vars = sponge_var.squeeze(3).unwrap();
+        sponge_var.absorb(&input_vars[3..]).unwrap();
+        out_vars.extend(sponge_var.squeeze(2).unwrap());
+
+        assert_eq!(out_vars.value().unwrap(), native_out);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_hash_gadget_rejects_wrong_output() {
+        use ark_r1cs_std::eq::EqGadget;
+
+        let hasher = PoseidonHash::new();
+        let gadget = PoseidonHashGadget::from_native(&hasher);
+        let inputs = [ScalarField::from(1u64), ScalarField::from(2u64)];
+
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        let input_vars = inputs
+            .iter()
+            .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let claimed = FpVar::new_input(cs.clone(), || Ok(hasher.hash(&inputs) + ScalarField::from(1u64)))
+            .unwrap();
+
+        gadget.hash(&input_vars).unwrap().enforce_equal(&claimed).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,70 @@
+//! PRF output gadgets
+//!
+//! In-circuit counterpart of [`crate::crypto::prf`].
+
+use crate::crypto::constraints::encoding::G1EncodingVar;
+use crate::crypto::constraints::poseidon::PoseidonHashGadget;
+use crate::crypto::poseidon::DomainTag;
+use crate::crypto::prf::ROUTING_VALUE_BITS;
+use crate::types::ScalarField;
+use ark_r1cs_std::{
+    convert::ToBitsGadget,
+    fields::{fp::FpVar, FieldVar},
+};
+use ark_relations::gr1cs::SynthesisError;
+
+/// Extract the routing value ρ from an encoded PRF output
+///
+/// Mirrors [`crate::crypto::prf::extract_routing_value`]. The digest is
+/// decomposed with `to_bits_le`, which enforces the canonical (< r) bit
+/// representation, so ρ is uniquely determined by φ.
+pub fn extract_routing_value_gadget(
+    gadget: &PoseidonHashGadget,
+    phi: &G1EncodingVar,
+) -> Result<FpVar<ScalarField>, SynthesisError> {
+    let digest = gadget.hash_with_domain(DomainTag::RoutingValue, &phi.to_field_elements())?;
+    let bits = digest.to_bits_le()?;
+
+    let mut rho = FpVar::zero();
+    let mut coeff = ScalarField::from(1u64);
+    for bit in bits.iter().take(ROUTING_VALUE_BITS) {
+        rho += FpVar::from(bit.clone()) * coeff;
+        coeff = coeff + coeff;
+    }
+    Ok(rho)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::prf::{compute_prf, extract_routing_value};
+    use crate::crypto::PoseidonHash;
+    use ark_bls12_381::G1Projective;
+    use ark_ec::{CurveGroup, PrimeGroup};
+    use ark_r1cs_std::{alloc::AllocVar, R1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_routing_value_gadget_matches_native() {
+        let mut rng = thread_rng();
+        let (sk, _pk) = keygen(&mut rng);
+        let generator = G1Projective::generator().into_affine();
+        let gadget = PoseidonHashGadget::from_native(&PoseidonHash::new());
+
+        for theta in [1u64, 2, 3] {
+            let phi = compute_prf(&ScalarField::from(theta), &sk, &generator).unwrap();
+
+            let cs = ConstraintSystem::<ScalarField>::new_ref();
+            let phi_var = G1EncodingVar::new_input(cs.clone(), || Ok(phi.phi)).unwrap();
+            let rho_var = extract_routing_value_gadget(&gadget, &phi_var).unwrap();
+
+            assert_eq!(
+                rho_var.value().unwrap(),
+                ScalarField::from(extract_routing_value(&phi) as u64)
+            );
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -2,12 +2,31 @@
 //!
 //! Operations on G1 and G2 groups
 
-use crate::types::{DiversifiedPublicKey, Diversifier, PublicKey, ScalarField, SecretKey};
-use ark_bls12_381::G2Projective;
+use crate::types::{
+    DiversifiedPublicKey, Diversifier, G1Point, G2Point, ProtocolError, ProtocolResult, PublicKey,
+    ScalarField, SecretKey,
+};
+use ark_bls12_381::{g1, g2, G1Projective, G2Projective};
+use ark_ec::hashing::{
+    curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve,
+};
 use ark_ec::{CurveGroup, PrimeGroup};
-use ark_ff::Field;
+use ark_ff::{field_hashers::DefaultFieldHasher, Field};
 use ark_std::UniformRand;
 use rand::Rng;
+use sha2::Sha256;
+
+/// Default domain separation tag for hashing to G1
+pub const DEFAULT_G1_DST: &[u8] = b"ZKBROWNIAN-V01-CS01-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
+/// Default domain separation tag for hashing to G2
+pub const DEFAULT_G2_DST: &[u8] = b"ZKBROWNIAN-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+/// RFC 9380 hasher for the BLS12381G1_XMD:SHA-256_SSWU_RO_ suite
+type G1Hasher = MapToCurveBasedHasher<G1Projective, DefaultFieldHasher<Sha256, 128>, WBMap<g1::Config>>;
+
+/// RFC 9380 hasher for the BLS12381G2_XMD:SHA-256_SSWU_RO_ suite
+type G2Hasher = MapToCurveBasedHasher<G2Projective, DefaultFieldHasher<Sha256, 128>, WBMap<g2::Config>>;
 
 /// Key generation: generate (sk, pk) pair where pk = G^sk in G2
 pub fn keygen<R: Rng>(rng: &mut R) -> (SecretKey, PublicKey) {
@@ -46,6 +65,32 @@ pub fn check_diversified_ownership(sk: &SecretKey, ppk: &DiversifiedPublicKey) -
     expected == ppk.ppk_1
 }
 
+/// Hash a message to G1 (RFC 9380, BLS12381G1_XMD:SHA-256_SSWU_RO_)
+///
+/// Simplified SWU onto the 11-isogenous curve, the isogeny back to E, and
+/// cofactor clearing. `dst` is the domain separation tag; use
+/// [`DEFAULT_G1_DST`] unless a protocol step needs its own.
+pub fn hash_to_g1(msg: &[u8], dst: &[u8]) -> ProtocolResult<G1Point> {
+    let hasher = G1Hasher::new(dst)
+        .map_err(|e| ProtocolError::CryptoError(format!("hash to G1: {}", e)))?;
+    hasher
+        .hash(msg)
+        .map_err(|e| ProtocolError::CryptoError(format!("hash to G1: {}", e)))
+}
+
+/// Hash a message to G2 (RFC 9380, BLS12381G2_XMD:SHA-256_SSWU_RO_)
+///
+/// Simplified SWU onto the 3-isogenous curve, the isogeny back to E', and
+/// cofactor clearing. `dst` is the domain separation tag; use
+/// [`DEFAULT_G2_DST`] unless a protocol step needs its own.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> ProtocolResult<G2Point> {
+    let hasher = G2Hasher::new(dst)
+        .map_err(|e| ProtocolError::CryptoError(format!("hash to G2: {}", e)))?;
+    hasher
+        .hash(msg)
+        .map_err(|e| ProtocolError::CryptoError(format!("hash to G2: {}", e)))
+}
+
 /// Scalar field operations
 
 /// Compute modular inverse in scalar field
@@ -63,8 +108,25 @@ pub fn compute_prf_exponent(theta: &ScalarField, sk: &ScalarField) -> Option<Sca
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ark_bls12_381::{Fq, Fq2};
+    use ark_ec::AffineRepr;
+    use ark_ff::PrimeField;
     use rand::thread_rng;
 
+    /// DST used by the RFC 9380 test vectors (Appendix J.9.1)
+    const RFC_G1_DST: &[u8] = b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+
+    /// DST used by the RFC 9380 test vectors (Appendix J.10.1)
+    const RFC_G2_DST: &[u8] = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+    fn fq(hex: &str) -> Fq {
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        Fq::from_be_bytes_mod_order(&bytes)
+    }
+
     #[test]
     fn test_keygen() {
         let mut rng = thread_rng();
@@ -105,4 +167,53 @@ mod tests {
         let product = sum * exponent;
         assert_eq!(product, ScalarField::from(1u64));
     }
+
+    #[test]
+    fn test_hash_to_g1_rfc_vectors() {
+        let vectors = [
+            (
+                "",
+                "052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a1",
+                "08ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265",
+            ),
+            (
+                "abc",
+                "03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f6903",
+                "0b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d",
+            ),
+        ];
+
+        for (msg, x, y) in vectors {
+            let point = hash_to_g1(msg.as_bytes(), RFC_G1_DST).unwrap();
+            assert_eq!(point, G1Point::new(fq(x), fq(y)), "msg = {:?}", msg);
+        }
+    }
+
+    #[test]
+    fn test_hash_to_g2_rfc_vectors() {
+        let point = hash_to_g2(b"", RFC_G2_DST).unwrap();
+        let expected = G2Point::new(
+            Fq2::new(
+                fq("0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a"),
+                fq("05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d"),
+            ),
+            Fq2::new(
+                fq("0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92"),
+                fq("12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6"),
+            ),
+        );
+        assert_eq!(point, expected);
+    }
+
+    #[test]
+    fn test_hash_to_curve_domain_separation() {
+        let a = hash_to_g2(b"msg", DEFAULT_G2_DST).unwrap();
+        let b = hash_to_g2(b"msg", b"ZKBROWNIAN-V01-OTHER").unwrap();
+        assert_ne!(a, b);
+        assert!(a.is_in_correct_subgroup_assuming_on_curve());
+
+        let c = hash_to_g1(b"msg", DEFAULT_G1_DST).unwrap();
+        assert!(c.is_in_correct_subgroup_assuming_on_curve());
+        assert!(!c.is_zero());
+    }
 }
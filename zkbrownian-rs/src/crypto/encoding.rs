@@ -0,0 +1,193 @@
+//! Canonical encodings of curve points into scalar field elements
+//!
+//! Coordinates of BLS12-381 points live in Fq (381 bits), which does not fit
+//! into the 255-bit scalar field Fr that Poseidon and the circuits work over.
+//! The x-coordinate is therefore split into 128-bit little-endian limbs and
+//! the point is completed by a flag element carrying the sign of y and the
+//! point-at-infinity marker:
+//!
+//! ```text
+//! encode(P) = [x_0, x_1, x_2, flags]
+//! x = x_0 + 2^128 · x_1 + 2^256 · x_2,   flags = sign + 2 · infinity
+//! ```
+//!
+//! `sign` is set when y is the lexicographically larger of ±y. Since x and
+//! the sign of y determine an affine point, the encoding is injective. The
+//! point at infinity encodes as `[0, 0, 0, 2]`.
+//!
+//! G2 points have coordinates in Fq2 = Fq[u]; both components of x are split
+//! the same way, giving `[x.c0 limbs, x.c1 limbs, flags]`.
+
+use crate::types::{G1Point, G2Point, ScalarField};
+use ark_bls12_381::{Fq, Fq2};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+
+/// Number of bits in each Fq limb
+pub const FQ_LIMB_BITS: usize = 128;
+
+/// Number of limbs needed to hold an Fq element
+pub const FQ_NUM_LIMBS: usize = 3;
+
+/// Number of bits in the most significant Fq limb (381 - 2 · 128)
+pub const FQ_TOP_LIMB_BITS: usize = Fq::MODULUS_BIT_SIZE as usize - 2 * FQ_LIMB_BITS;
+
+/// Number of scalar field elements in the encoding of a G1 point
+pub const G1_ENCODING_LEN: usize = FQ_NUM_LIMBS + 1;
+
+/// Number of scalar field elements in the encoding of a G2 point
+pub const G2_ENCODING_LEN: usize = 2 * FQ_NUM_LIMBS + 1;
+
+/// Flag bit set when y is the larger of ±y
+pub const FLAG_SIGN: u64 = 1;
+
+/// Flag bit set for the point at infinity
+pub const FLAG_INFINITY: u64 = 2;
+
+/// Canonical encoding of a G1 point: x-coordinate limbs followed by the flags
+pub type G1Encoding = [ScalarField; G1_ENCODING_LEN];
+
+/// Canonical encoding of a G2 point: limbs of x.c0 and x.c1, then the flags
+pub type G2Encoding = [ScalarField; G2_ENCODING_LEN];
+
+/// Split a base field element into little-endian 128-bit limbs
+pub fn fq_to_limbs(x: &Fq) -> [ScalarField; FQ_NUM_LIMBS] {
+    let words = x.into_bigint().0;
+    let mut limbs = [ScalarField::from(0u64); FQ_NUM_LIMBS];
+    for (i, limb) in limbs.iter_mut().enumerate() {
+        let value = (words[2 * i] as u128) | ((words[2 * i + 1] as u128) << 64);
+        *limb = ScalarField::from(value);
+    }
+    limbs
+}
+
+/// Whether `y` is the lexicographically larger square root, i.e. y > -y
+pub fn fq_sign(y: &Fq) -> bool {
+    *y > -*y
+}
+
+/// Whether `y` is the lexicographically larger square root in Fq2
+pub fn fq2_sign(y: &Fq2) -> bool {
+    *y > -*y
+}
+
+/// Encode a G1 point as scalar field elements
+pub fn encode_g1(point: &G1Point) -> G1Encoding {
+    let mut encoding = [ScalarField::from(0u64); G1_ENCODING_LEN];
+
+    if point.is_zero() {
+        encoding[FQ_NUM_LIMBS] = ScalarField::from(FLAG_INFINITY);
+        return encoding;
+    }
+
+    encoding[..FQ_NUM_LIMBS].copy_from_slice(&fq_to_limbs(&point.x));
+    if fq_sign(&point.y) {
+        encoding[FQ_NUM_LIMBS] = ScalarField::from(FLAG_SIGN);
+    }
+    encoding
+}
+
+/// Encode a G2 point as scalar field elements
+pub fn encode_g2(point: &G2Point) -> G2Encoding {
+    let mut encoding = [ScalarField::from(0u64); G2_ENCODING_LEN];
+    let flags = 2 * FQ_NUM_LIMBS;
+
+    if point.is_zero() {
+        encoding[flags] = ScalarField::from(FLAG_INFINITY);
+        return encoding;
+    }
+
+    encoding[..FQ_NUM_LIMBS].copy_from_slice(&fq_to_limbs(&point.x.c0));
+    encoding[FQ_NUM_LIMBS..flags].copy_from_slice(&fq_to_limbs(&point.x.c1));
+    if fq2_sign(&point.y) {
+        encoding[flags] = ScalarField::from(FLAG_SIGN);
+    }
+    encoding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{G1Projective, G2Projective};
+    use ark_ec::CurveGroup;
+    use ark_ff::{BigInteger, Field};
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_encode_identity() {
+        let encoding = encode_g1(&G1Point::zero());
+        assert_eq!(
+            encoding,
+            [
+                ScalarField::from(0u64),
+                ScalarField::from(0u64),
+                ScalarField::from(0u64),
+                ScalarField::from(FLAG_INFINITY),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_limbs_recompose() {
+        let mut rng = thread_rng();
+        let point = G1Projective::rand(&mut rng).into_affine();
+        let limbs = fq_to_limbs(&point.x);
+
+        // Recompose the limbs over the integers via Fq arithmetic
+        let shift = Fq::from(2u64).pow([FQ_LIMB_BITS as u64]);
+        let mut recomposed = Fq::from(0u64);
+        for limb in limbs.iter().rev() {
+            recomposed = recomposed * shift + Fq::from_le_bytes_mod_order(&limb.into_bigint().to_bytes_le());
+        }
+        assert_eq!(recomposed, point.x);
+
+        // The top limb must fit into the remaining bits
+        assert!(limbs[FQ_NUM_LIMBS - 1].into_bigint().num_bits() as usize <= FQ_TOP_LIMB_BITS);
+    }
+
+    #[test]
+    fn test_encoding_distinguishes_negation() {
+        let mut rng = thread_rng();
+        let point = G1Projective::rand(&mut rng).into_affine();
+        let neg = -point;
+
+        let a = encode_g1(&point);
+        let b = encode_g1(&neg);
+        assert_eq!(a[..FQ_NUM_LIMBS], b[..FQ_NUM_LIMBS]);
+        assert_ne!(a[FQ_NUM_LIMBS], b[FQ_NUM_LIMBS]);
+    }
+
+    #[test]
+    fn test_encoding_injective_on_samples() {
+        let mut rng = thread_rng();
+        let points: Vec<G1Point> = (0..16)
+            .map(|_| G1Projective::rand(&mut rng).into_affine())
+            .collect();
+
+        for i in 0..points.len() {
+            for j in (i + 1)..points.len() {
+                assert_ne!(encode_g1(&points[i]), encode_g1(&points[j]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_g2() {
+        let mut rng = thread_rng();
+        let point = G2Projective::rand(&mut rng).into_affine();
+        let neg = -point;
+
+        let a = encode_g2(&point);
+        let b = encode_g2(&neg);
+        assert_eq!(a[..2 * FQ_NUM_LIMBS], b[..2 * FQ_NUM_LIMBS]);
+        assert_ne!(a[2 * FQ_NUM_LIMBS], b[2 * FQ_NUM_LIMBS]);
+        assert_eq!(a[..FQ_NUM_LIMBS], fq_to_limbs(&point.x.c0));
+
+        let other = G2Projective::rand(&mut rng).into_affine();
+        assert_ne!(a, encode_g2(&other));
+
+        let identity = encode_g2(&G2Point::zero());
+        assert_eq!(identity[2 * FQ_NUM_LIMBS], ScalarField::from(FLAG_INFINITY));
+    }
+}
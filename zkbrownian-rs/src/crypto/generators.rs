@@ -2,12 +2,19 @@
 //!
 //! Pre-generates random generators G_i (in G1) and H_i (in G2)
 //! as required by the protocol specification
+//!
+//! Protocol parameters should come from [`Generators::from_seed`], which
+//! hashes a public label and an index to the curve. Nobody knows discrete
+//! logarithms between the resulting points, and anyone can recompute them
+//! with [`Generators::verify_seed`].
 
-use crate::types::{G1Point, G2Point};
+use crate::crypto::curve_ops::{hash_to_g1, hash_to_g2};
+use crate::types::{G1Point, G2Point, ProtocolResult};
 use ark_bls12_381::{G1Projective, G2Projective};
 use ark_ec::{CurveGroup, PrimeGroup};
 use ark_std::UniformRand;
 use rand::Rng;
+use std::sync::OnceLock;
 
 /// Generate N random generators in G1
 pub fn generate_g1_generators<R: Rng>(rng: &mut R, count: usize) -> Vec<G1Point> {
@@ -23,7 +30,62 @@ pub fn generate_g2_generators<R: Rng>(rng: &mut R, count: usize) -> Vec<G2Point>
         .collect()
 }
 
+/// Domain separation tag for seeded G1 generators
+pub const G1_GENERATORS_DST: &[u8] =
+    b"ZKBROWNIAN-V01-CS01-with-BLS12381G1_XMD:SHA-256_SSWU_RO_GENERATORS_";
+
+/// Domain separation tag for seeded G2 generators
+pub const G2_GENERATORS_DST: &[u8] =
+    b"ZKBROWNIAN-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_GENERATORS_";
+
+/// Seed label of the protocol-wide generators
+pub const PROTOCOL_GENERATORS_LABEL: &[u8] = b"zkbrownian/generators/v1";
+
+/// Number of additional G1 generators in the protocol parameters
+pub const PROTOCOL_NUM_G1: usize = 9;
+
+/// Number of additional G2 generators in the protocol parameters
+pub const PROTOCOL_NUM_G2: usize = 4;
+
+/// The protocol-wide generators, derived once and cached
+///
+/// Equal to `Generators::protocol(PROTOCOL_NUM_G1, PROTOCOL_NUM_G2)`.
+pub fn protocol_generators() -> &'static Generators {
+    static GENERATORS: OnceLock<Generators> = OnceLock::new();
+    GENERATORS.get_or_init(|| {
+        Generators::protocol(PROTOCOL_NUM_G1, PROTOCOL_NUM_G2)
+            .expect("hashing to the curve with a fixed DST does not fail")
+    })
+}
+
+/// Hash-to-curve input for the generator with the given index
+///
+/// The label is length-prefixed so that distinct (label, index) pairs never
+/// produce the same message.
+fn seed_message(label: &[u8], index: u32) -> Vec<u8> {
+    let mut msg = Vec::with_capacity(label.len() + 12);
+    msg.extend_from_slice(&(label.len() as u64).to_be_bytes());
+    msg.extend_from_slice(label);
+    msg.extend_from_slice(&index.to_be_bytes());
+    msg
+}
+
+/// Derive N generators in G1 from a public label
+pub fn seeded_g1_generators(label: &[u8], count: usize) -> ProtocolResult<Vec<G1Point>> {
+    (0..count as u32)
+        .map(|i| hash_to_g1(&seed_message(label, i), G1_GENERATORS_DST))
+        .collect()
+}
+
+/// Derive N generators in G2 from a public label
+pub fn seeded_g2_generators(label: &[u8], count: usize) -> ProtocolResult<Vec<G2Point>> {
+    (0..count as u32)
+        .map(|i| hash_to_g2(&seed_message(label, i), G2_GENERATORS_DST))
+        .collect()
+}
+
 /// Standard generators
+#[derive(Clone, Debug, PartialEq)]
 pub struct Generators {
     /// Base generator for G1 (standard curve generator)
     pub g1_base: G1Point,
@@ -38,6 +100,10 @@ pub struct Generators {
 impl Generators {
     /// Generate standard generators for the protocol
     ///
+    /// The additional generators are sampled from `rng`, so nobody else can
+    /// check that their discrete logarithms are unknown. Use
+    /// [`Generators::from_seed`] for public parameters.
+    ///
     /// # Arguments
     /// * `num_g1` - Number of additional G1 generators needed
     /// * `num_g2` - Number of additional G2 generators needed
@@ -50,6 +116,38 @@ impl Generators {
         }
     }
 
+    /// Derive generators deterministically from a public label
+    ///
+    /// The base generators are the standard curve generators; the additional
+    /// ones are `hash_to_g1(label, i)` and `hash_to_g2(label, i)` for
+    /// i = 0, 1, ... (see [`seeded_g1_generators`]).
+    ///
+    /// # Arguments
+    /// * `label` - Public seed label
+    /// * `num_g1` - Number of additional G1 generators needed
+    /// * `num_g2` - Number of additional G2 generators needed
+    pub fn from_seed(label: &[u8], num_g1: usize, num_g2: usize) -> ProtocolResult<Self> {
+        Ok(Self {
+            g1_base: G1Projective::generator().into_affine(),
+            g2_base: G2Projective::generator().into_affine(),
+            g1_generators: seeded_g1_generators(label, num_g1)?,
+            g2_generators: seeded_g2_generators(label, num_g2)?,
+        })
+    }
+
+    /// Protocol-wide generators, seeded with [`PROTOCOL_GENERATORS_LABEL`]
+    pub fn protocol(num_g1: usize, num_g2: usize) -> ProtocolResult<Self> {
+        Self::from_seed(PROTOCOL_GENERATORS_LABEL, num_g1, num_g2)
+    }
+
+    /// Check that these generators were derived from `label`
+    ///
+    /// Recomputes every generator with [`Generators::from_seed`] and compares.
+    pub fn verify_seed(&self, label: &[u8]) -> bool {
+        Self::from_seed(label, self.g1_generators.len(), self.g2_generators.len())
+            .is_ok_and(|expected| expected == *self)
+    }
+
     /// Get a specific G1 generator by index
     pub fn g1(&self, index: usize) -> Option<&G1Point> {
         if index == 0 {
@@ -118,4 +216,47 @@ mod tests {
         assert!(generators.g1(1).is_some());
         assert!(generators.g2(1).is_some());
     }
+
+    #[test]
+    fn test_generators_from_seed() {
+        let generators = Generators::from_seed(b"test-label", 4, 3).unwrap();
+        assert_eq!(generators.g1_generators.len(), 4);
+        assert_eq!(generators.g2_generators.len(), 3);
+
+        // Deterministic, and a prefix of a longer derivation
+        let again = Generators::from_seed(b"test-label", 6, 3).unwrap();
+        assert_eq!(generators.g1_generators[..], again.g1_generators[..4]);
+        assert_eq!(generators.g2_generators, again.g2_generators);
+
+        for i in 0..generators.g1_generators.len() {
+            assert_ne!(generators.g1_generators[i], generators.g1_base);
+            for j in (i + 1)..generators.g1_generators.len() {
+                assert_ne!(generators.g1_generators[i], generators.g1_generators[j]);
+            }
+        }
+
+        let other = Generators::from_seed(b"other-label", 4, 3).unwrap();
+        assert_ne!(generators.g1_generators[0], other.g1_generators[0]);
+        assert_ne!(generators.g2_generators[0], other.g2_generators[0]);
+    }
+
+    #[test]
+    fn test_generators_verify_seed() {
+        let generators = Generators::protocol(3, 2).unwrap();
+        assert!(generators.verify_seed(PROTOCOL_GENERATORS_LABEL));
+        assert!(!generators.verify_seed(b"other-label"));
+
+        // Generators sampled from an RNG cannot be traced back to a seed
+        let mut rng = thread_rng();
+        let sampled = Generators::generate(&mut rng, 3, 2);
+        assert!(!sampled.verify_seed(PROTOCOL_GENERATORS_LABEL));
+
+        let cached = protocol_generators();
+        assert!(cached.verify_seed(PROTOCOL_GENERATORS_LABEL));
+        assert_eq!(cached.g2_generators[..2], generators.g2_generators[..]);
+
+        let mut tampered = generators.clone();
+        tampered.g2_generators[1] = tampered.g2_base;
+        assert!(!tampered.verify_seed(PROTOCOL_GENERATORS_LABEL));
+    }
 }
@@ -0,0 +1,366 @@
+//! Poseidon Merkle tree over (pk, md_2) leaves
+//!
+//! The membership proofs π_1 and π_3 show (pk, md_{2,k}) ∈ MT(md_1): the
+//! tree commits to every registered public key together with the metadata
+//! digest md_2 of that node, and md_1 is its root.
+//!
+//! A leaf is Hash_{MerkleLeaf}(encode_g2(pk), md_2), with the key given by
+//! its canonical limb encoding (see [`crate::crypto::encoding`]), and an
+//! inner node is Hash_{MerkleNode}(left, right). The tree has the fixed
+//! depth [`MERKLE_DEPTH`], enough for [`NUM_NODES`] leaves, so every path has
+//! the same length inside a circuit. Unused leaves are [`EMPTY_LEAF`].
+//!
+//! The metadata digest md_{2,k} of node k is the root of its neighbour tree
+//! ([`MerkleTree::from_neighbours`]). Leaf j is
+//! Hash_{NeighbourLeaf}(encode_g2(pk_j), v_j), where v_j = w_1 + ... + w_j is
+//! the upper end of the weight bucket [v_{j-1}, v_j) that routes to
+//! neighbour j (v_0 = 0).
+//!
+//! The Pedersen commitments C_1 and C_3 of the spec commit to the values a
+//! leaf hashes, (encode_g2(pk), md_2) or (encode_g2(pk_j), v_j), in the
+//! order of [`leaf_values`].
+
+use crate::crypto::encoding::{encode_g2, G2_ENCODING_LEN};
+use crate::crypto::poseidon::{DomainTag, PoseidonHash};
+use crate::types::{ProtocolError, ProtocolResult, PublicKey, ScalarField};
+use crate::{MAX_OUT_DEGREE, NUM_NODES};
+use ark_ff::AdditiveGroup;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+/// Depth of the key tree: ⌈log2(NUM_NODES)⌉
+pub const MERKLE_DEPTH: usize = NUM_NODES.next_power_of_two().trailing_zeros() as usize;
+
+/// Depth of a neighbour tree: ⌈log2(MAX_OUT_DEGREE)⌉
+pub const NEIGHBOUR_DEPTH: usize = MAX_OUT_DEGREE.next_power_of_two().trailing_zeros() as usize;
+
+/// Value of unused leaves
+pub const EMPTY_LEAF: ScalarField = ScalarField::ZERO;
+
+/// Leaf for a public key and its metadata digest: Hash(encode_g2(pk), md_2)
+pub fn merkle_leaf(hasher: &PoseidonHash, pk: &PublicKey, md: &ScalarField) -> ScalarField {
+    let mut inputs = encode_g2(&pk.pk).to_vec();
+    inputs.push(*md);
+    hasher.hash_with_domain(DomainTag::MerkleLeaf, &inputs)
+}
+
+/// Neighbour tree leaf: Hash(encode_g2(pk_j), v_j)
+pub fn neighbour_leaf(hasher: &PoseidonHash, pk: &PublicKey, bound: u64) -> ScalarField {
+    let mut inputs = encode_g2(&pk.pk).to_vec();
+    inputs.push(ScalarField::from(bound));
+    hasher.hash_with_domain(DomainTag::NeighbourLeaf, &inputs)
+}
+
+/// Upper ends v_j = w_1 + ... + w_j of the weight buckets
+pub fn cumulative_weights(weights: impl IntoIterator<Item = u32>) -> Vec<u64> {
+    weights
+        .into_iter()
+        .scan(0u64, |sum, weight| {
+            *sum += weight as u64;
+            Some(*sum)
+        })
+        .collect()
+}
+
+/// Inner node: Hash(left, right)
+pub fn merkle_node(hasher: &PoseidonHash, left: &ScalarField, right: &ScalarField) -> ScalarField {
+    hasher.hash_with_domain(DomainTag::MerkleNode, &[*left, *right])
+}
+
+/// Hiding commitment to a leaf: Hash(leaf, r)
+///
+/// The membership circuits publish this instead of the leaf, so the key
+/// stays hidden while later proof components can refer to it.
+pub fn commit_leaf(hasher: &PoseidonHash, leaf: &ScalarField, r: &ScalarField) -> ScalarField {
+    hasher.hash_with_domain(DomainTag::LeafCommitment, &[*leaf, *r])
+}
+
+/// Number of values a leaf hashes: the encoding of a key and one scalar
+pub const LEAF_VALUES: usize = G2_ENCODING_LEN + 1;
+
+/// The values a leaf hashes, encode_g2(pk) followed by md_2 or v_j
+pub fn leaf_values(pk: &PublicKey, value: ScalarField) -> Vec<ScalarField> {
+    let mut values = encode_g2(&pk.pk).to_vec();
+    values.push(value);
+    values
+}
+
+/// Fixed-depth binary Merkle tree
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleTree {
+    /// layers[0] are the 2^depth leaves, the last layer is the root
+    layers: Vec<Vec<ScalarField>>,
+}
+
+/// Authentication path from a leaf to the root
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct MerklePath {
+    /// Leaf index; bit j says whether the node at level j is a right child
+    pub index: u64,
+    /// Siblings from the leaf level up
+    pub siblings: Vec<ScalarField>,
+}
+
+impl MerkleTree {
+    /// Build a tree of the given depth, padding the leaves with [`EMPTY_LEAF`]
+    pub fn new(
+        hasher: &PoseidonHash,
+        leaves: &[ScalarField],
+        depth: usize,
+    ) -> ProtocolResult<Self> {
+        let width = 1usize
+            .checked_shl(depth as u32)
+            .filter(|width| leaves.len() <= *width)
+            .ok_or_else(|| {
+                ProtocolError::CryptoError(format!(
+                    "{} leaves do not fit in a tree of depth {}",
+                    leaves.len(),
+                    depth
+                ))
+            })?;
+
+        let mut layer = leaves.to_vec();
+        layer.resize(width, EMPTY_LEAF);
+
+        let mut layers = Vec::with_capacity(depth + 1);
+        while layer.len() > 1 {
+            let parent = layer
+                .chunks(2)
+                .map(|pair| merkle_node(hasher, &pair[0], &pair[1]))
+                .collect();
+            layers.push(std::mem::replace(&mut layer, parent));
+        }
+        layers.push(layer);
+
+        Ok(Self { layers })
+    }
+
+    /// Key tree MT(md_1) over `all_public_keys` and their metadata digests
+    pub fn from_public_keys(
+        hasher: &PoseidonHash,
+        all_public_keys: &[PublicKey],
+        metadata: &[ScalarField],
+    ) -> ProtocolResult<Self> {
+        if all_public_keys.len() != metadata.len() {
+            return Err(ProtocolError::CryptoError(format!(
+                "{} public keys but {} metadata digests",
+                all_public_keys.len(),
+                metadata.len()
+            )));
+        }
+
+        let leaves: Vec<ScalarField> = all_public_keys
+            .iter()
+            .zip(metadata)
+            .map(|(pk, md)| merkle_leaf(hasher, pk, md))
+            .collect();
+        Self::new(hasher, &leaves, MERKLE_DEPTH)
+    }
+
+    /// Neighbour tree of a node, whose root is its md_2
+    ///
+    /// `neighbours` lists the (pk_j, w_j) in routing order.
+    pub fn from_neighbours(
+        hasher: &PoseidonHash,
+        neighbours: &[(PublicKey, u32)],
+    ) -> ProtocolResult<Self> {
+        let bounds = cumulative_weights(neighbours.iter().map(|(_, weight)| *weight));
+        let leaves: Vec<ScalarField> = neighbours
+            .iter()
+            .zip(bounds)
+            .map(|((pk, _), bound)| neighbour_leaf(hasher, pk, bound))
+            .collect();
+        Self::new(hasher, &leaves, NEIGHBOUR_DEPTH)
+    }
+
+    /// Depth of the tree
+    pub fn depth(&self) -> usize {
+        self.layers.len() - 1
+    }
+
+    /// The root md_1
+    pub fn root(&self) -> ScalarField {
+        self.layers[self.depth()][0]
+    }
+
+    /// Leaf at `index`, if it is inside the tree
+    pub fn leaf(&self, index: usize) -> Option<ScalarField> {
+        self.layers[0].get(index).copied()
+    }
+
+    /// Authentication path for the leaf at `index`
+    pub fn path(&self, index: usize) -> ProtocolResult<MerklePath> {
+        if index >= self.layers[0].len() {
+            return Err(ProtocolError::CryptoError(format!(
+                "leaf index {} outside a tree of {} leaves",
+                index,
+                self.layers[0].len()
+            )));
+        }
+
+        let siblings = self.layers[..self.depth()]
+            .iter()
+            .enumerate()
+            .map(|(level, layer)| layer[(index >> level) ^ 1])
+            .collect();
+
+        Ok(MerklePath {
+            index: index as u64,
+            siblings,
+        })
+    }
+}
+
+impl MerklePath {
+    /// Whether the node at `level` is the right child of its parent
+    pub fn is_right(&self, level: usize) -> bool {
+        self.index.checked_shr(level as u32).unwrap_or(0) & 1 == 1
+    }
+
+    /// Hash `leaf` up to the root along the path
+    pub fn compute_root(&self, hasher: &PoseidonHash, leaf: &ScalarField) -> ScalarField {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(*leaf, |node, (level, sibling)| {
+                if self.is_right(level) {
+                    merkle_node(hasher, sibling, &node)
+                } else {
+                    merkle_node(hasher, &node, sibling)
+                }
+            })
+    }
+
+    /// Check that `leaf` sits at `self.index` in the tree with root `root`
+    pub fn verify(&self, hasher: &PoseidonHash, root: &ScalarField, leaf: &ScalarField) -> bool {
+        // Index bits above the depth would alias another leaf
+        self.index
+            .checked_shr(self.siblings.len() as u32)
+            .unwrap_or(0)
+            == 0
+            && self.compute_root(hasher, leaf) == *root
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    /// A key tree of `n` random keys with random metadata
+    pub(crate) fn key_tree(
+        n: usize,
+    ) -> (PoseidonHash, Vec<PublicKey>, Vec<ScalarField>, MerkleTree) {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let pks: Vec<PublicKey> = (0..n).map(|_| keygen(&mut rng).1).collect();
+        let metadata: Vec<ScalarField> = (0..n).map(|_| ScalarField::rand(&mut rng)).collect();
+        let tree = MerkleTree::from_public_keys(&hasher, &pks, &metadata).unwrap();
+        (hasher, pks, metadata, tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::key_tree;
+    use super::*;
+
+    #[test]
+    fn test_paths_verify() {
+        let (hasher, pks, metadata, tree) = key_tree(5);
+        assert_eq!(tree.depth(), MERKLE_DEPTH);
+        assert_eq!(MERKLE_DEPTH, 8);
+
+        for (k, (pk, md)) in pks.iter().zip(&metadata).enumerate() {
+            let leaf = merkle_leaf(&hasher, pk, md);
+            assert_eq!(tree.leaf(k), Some(leaf));
+
+            let path = tree.path(k).unwrap();
+            assert_eq!(path.siblings.len(), MERKLE_DEPTH);
+            assert!(path.verify(&hasher, &tree.root(), &leaf));
+        }
+
+        // Padding leaves have paths too
+        let path = tree.path(NUM_NODES - 1).unwrap();
+        assert!(path.verify(&hasher, &tree.root(), &EMPTY_LEAF));
+        assert!(tree.path(NUM_NODES).is_err());
+    }
+
+    #[test]
+    fn test_paths_reject_wrong_leaf() {
+        let (hasher, pks, metadata, tree) = key_tree(4);
+        let leaf = merkle_leaf(&hasher, &pks[1], &metadata[1]);
+        let path = tree.path(1).unwrap();
+
+        // Another key, another metadata digest, another position
+        assert!(!path.verify(
+            &hasher,
+            &tree.root(),
+            &merkle_leaf(&hasher, &pks[2], &metadata[1])
+        ));
+        assert!(!path.verify(
+            &hasher,
+            &tree.root(),
+            &merkle_leaf(&hasher, &pks[1], &metadata[2])
+        ));
+        let mut moved = path.clone();
+        moved.index = 3;
+        assert!(!moved.verify(&hasher, &tree.root(), &leaf));
+
+        // Index bits beyond the depth are not ignored
+        let mut aliased = path;
+        aliased.index += 1 << MERKLE_DEPTH;
+        assert_eq!(aliased.compute_root(&hasher, &leaf), tree.root());
+        assert!(!aliased.verify(&hasher, &tree.root(), &leaf));
+    }
+
+    #[test]
+    fn test_neighbour_tree() {
+        let (hasher, pks, _, _) = key_tree(3);
+        let neighbours: Vec<(PublicKey, u32)> = pks
+            .into_iter()
+            .zip([1u32 << 30, 1 << 31, 1 << 30])
+            .collect();
+        let tree = MerkleTree::from_neighbours(&hasher, &neighbours).unwrap();
+        assert_eq!(tree.depth(), NEIGHBOUR_DEPTH);
+        assert_eq!(NEIGHBOUR_DEPTH, 5);
+
+        let bounds = cumulative_weights(neighbours.iter().map(|(_, weight)| *weight));
+        assert_eq!(bounds, vec![1 << 30, 3 << 30, 1 << 32]);
+        for (j, (pk, _)) in neighbours.iter().enumerate() {
+            let leaf = neighbour_leaf(&hasher, pk, bounds[j]);
+            assert_eq!(tree.leaf(j), Some(leaf));
+            assert!(tree.path(j).unwrap().verify(&hasher, &tree.root(), &leaf));
+        }
+
+        // Neighbour leaves never collide with key tree leaves
+        let (pk, _) = &neighbours[0];
+        assert_ne!(
+            neighbour_leaf(&hasher, pk, 7),
+            merkle_leaf(&hasher, pk, &ScalarField::from(7u64))
+        );
+
+        let too_many = vec![neighbours[0].clone(); MAX_OUT_DEGREE + 1];
+        assert!(MerkleTree::from_neighbours(&hasher, &too_many).is_err());
+    }
+
+    #[test]
+    fn test_tree_construction() {
+        let hasher = PoseidonHash::new();
+        let leaves = [
+            ScalarField::from(1u64),
+            ScalarField::from(2u64),
+            ScalarField::from(3u64),
+        ];
+        let tree = MerkleTree::new(&hasher, &leaves, 2).unwrap();
+
+        let left = merkle_node(&hasher, &leaves[0], &leaves[1]);
+        let right = merkle_node(&hasher, &leaves[2], &EMPTY_LEAF);
+        assert_eq!(tree.root(), merkle_node(&hasher, &left, &right));
+
+        assert!(MerkleTree::new(&hasher, &leaves, 1).is_err());
+        let (_, pks, _, _) = key_tree(2);
+        assert!(MerkleTree::from_public_keys(&hasher, &pks, &[]).is_err());
+    }
+}
@@ -1,11 +1,21 @@
 //! Cryptographic primitives for ZK Brownian protocol
 
 pub mod poseidon;
+pub mod encoding;
 pub mod curve_ops;
 pub mod prf;
 pub mod generators;
+pub mod pedersen;
+pub mod merkle;
+
+/// R1CS gadgets matching the native primitives.
+#[cfg(feature = "r1cs")]
+pub mod constraints;
 
 pub use poseidon::*;
+pub use encoding::*;
 pub use curve_ops::*;
 pub use prf::*;
 pub use generators::*;
+pub use pedersen::*;
+pub use merkle::*;
@@ -0,0 +1,295 @@
+//! Pedersen vector commitments over G1 and G2
+//!
+//! C = Σ v_i · B_i + r · H for a vector of scalars v, bases B_i and a
+//! blinding base H. The bases come from [`Generators`], so as long as those
+//! are derived with [`Generators::from_seed`] nobody knows discrete logarithms
+//! between them and the commitment is binding.
+//!
+//! The spec uses both groups: C_1 and C_3 live in G1, while pk* = G^sk · H^r
+//! lives in G2 next to the public keys.
+
+use crate::crypto::generators::Generators;
+use crate::types::{ProtocolError, ProtocolResult, ScalarField};
+use ark_bls12_381::{G1Projective, G2Projective};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use rand::Rng;
+
+/// Commitment parameters: one base per vector slot plus a blinding base
+#[derive(Clone, Debug, PartialEq)]
+pub struct PedersenParams<C: CurveGroup<ScalarField = ScalarField>> {
+    /// Bases B_i for the committed values
+    pub bases: Vec<C::Affine>,
+    /// Blinding base H
+    pub blinding: C::Affine,
+}
+
+/// Pedersen parameters in G1
+pub type G1PedersenParams = PedersenParams<G1Projective>;
+
+/// Pedersen parameters in G2
+pub type G2PedersenParams = PedersenParams<G2Projective>;
+
+/// A Pedersen commitment
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PedersenCommitment<C: CurveGroup<ScalarField = ScalarField>> {
+    pub point: C::Affine,
+}
+
+/// Opening of a Pedersen commitment: the committed values and the randomness
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PedersenOpening {
+    pub values: Vec<ScalarField>,
+    pub randomness: ScalarField,
+}
+
+impl<C: CurveGroup<ScalarField = ScalarField>> PedersenCommitment<C> {
+    /// Homomorphic addition: commits to the sum of values and randomness
+    pub fn add(&self, other: &Self) -> Self {
+        Self {
+            point: (self.point.into_group() + other.point).into_affine(),
+        }
+    }
+}
+
+impl PedersenOpening {
+    /// Opening of the sum of two commitments
+    ///
+    /// The shorter value vector is padded with zeros.
+    pub fn add(&self, other: &Self) -> Self {
+        let len = self.values.len().max(other.values.len());
+        let values = (0..len)
+            .map(|i| {
+                self.values.get(i).copied().unwrap_or_default()
+                    + other.values.get(i).copied().unwrap_or_default()
+            })
+            .collect();
+
+        Self {
+            values,
+            randomness: self.randomness + other.randomness,
+        }
+    }
+}
+
+impl<C: CurveGroup<ScalarField = ScalarField>> PedersenParams<C> {
+    /// Create parameters from explicit bases
+    pub fn new(bases: Vec<C::Affine>, blinding: C::Affine) -> Self {
+        Self { bases, blinding }
+    }
+
+    /// Maximum number of values that can be committed to
+    pub fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    /// Whether the parameters have no value bases
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty()
+    }
+
+    /// Commit to `values` with randomness `r`
+    ///
+    /// Fewer values than bases are allowed; the remaining slots are zero.
+    pub fn commit(
+        &self,
+        values: &[ScalarField],
+        r: &ScalarField,
+    ) -> ProtocolResult<PedersenCommitment<C>> {
+        if values.len() > self.bases.len() {
+            return Err(ProtocolError::CryptoError(format!(
+                "Pedersen commitment to {} values with {} bases",
+                values.len(),
+                self.bases.len()
+            )));
+        }
+
+        let point = C::msm_unchecked(&self.bases[..values.len()], values) + self.blinding * *r;
+        Ok(PedersenCommitment {
+            point: point.into_affine(),
+        })
+    }
+
+    /// Commit to `values` with fresh randomness, returning the opening too
+    pub fn commit_random<R: Rng>(
+        &self,
+        values: &[ScalarField],
+        rng: &mut R,
+    ) -> ProtocolResult<(PedersenCommitment<C>, PedersenOpening)> {
+        let randomness = ScalarField::rand(rng);
+        let commitment = self.commit(values, &randomness)?;
+        Ok((
+            commitment,
+            PedersenOpening {
+                values: values.to_vec(),
+                randomness,
+            },
+        ))
+    }
+
+    /// Recompute the commitment from an opening
+    pub fn open(&self, opening: &PedersenOpening) -> ProtocolResult<PedersenCommitment<C>> {
+        self.commit(&opening.values, &opening.randomness)
+    }
+
+    /// Check that `opening` opens `commitment`
+    pub fn verify_opening(
+        &self,
+        commitment: &PedersenCommitment<C>,
+        opening: &PedersenOpening,
+    ) -> bool {
+        self.open(opening).is_ok_and(|c| c == *commitment)
+    }
+
+    /// Rerandomize a commitment: C' = C + r' · H
+    ///
+    /// Returns the new commitment and its updated opening.
+    pub fn rerandomize<R: Rng>(
+        &self,
+        commitment: &PedersenCommitment<C>,
+        opening: &PedersenOpening,
+        rng: &mut R,
+    ) -> (PedersenCommitment<C>, PedersenOpening) {
+        let delta = ScalarField::rand(rng);
+        let point = (commitment.point.into_group() + self.blinding * delta).into_affine();
+
+        (
+            PedersenCommitment { point },
+            PedersenOpening {
+                values: opening.values.clone(),
+                randomness: opening.randomness + delta,
+            },
+        )
+    }
+}
+
+impl PedersenParams<G1Projective> {
+    /// G1 parameters for `n` values: bases G_1..G_n, blinding base G_{n+1}
+    ///
+    /// Takes the first n + 1 entries of `g1_generators`.
+    pub fn from_generators(generators: &Generators, n: usize) -> ProtocolResult<Self> {
+        if generators.g1_generators.len() <= n {
+            return Err(ProtocolError::CryptoError(format!(
+                "need {} G1 generators, have {}",
+                n + 1,
+                generators.g1_generators.len()
+            )));
+        }
+
+        Ok(Self::new(
+            generators.g1_generators[..n].to_vec(),
+            generators.g1_generators[n],
+        ))
+    }
+}
+
+impl PedersenParams<G2Projective> {
+    /// G2 parameters for `n` values: bases G, H_1, ..., H_{n-1}, blinding base H
+    ///
+    /// The first base is the standard generator, so that committing to sk
+    /// gives pk* = G^sk · H^r with pk = G^sk. H is the first entry of
+    /// `g2_generators`, followed by H_1, H_2, ...
+    pub fn from_generators(generators: &Generators, n: usize) -> ProtocolResult<Self> {
+        if generators.g2_generators.len() < n.max(1) {
+            return Err(ProtocolError::CryptoError(format!(
+                "need {} G2 generators, have {}",
+                n.max(1),
+                generators.g2_generators.len()
+            )));
+        }
+
+        let mut bases = Vec::with_capacity(n);
+        if n > 0 {
+            bases.push(generators.g2_base);
+            bases.extend_from_slice(&generators.g2_generators[1..n]);
+        }
+
+        Ok(Self::new(bases, generators.g2_generators[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use rand::thread_rng;
+
+    fn generators() -> Generators {
+        Generators::from_seed(b"pedersen-test", 4, 3).unwrap()
+    }
+
+    #[test]
+    fn test_commit_and_verify_opening() {
+        let mut rng = thread_rng();
+        let params = G1PedersenParams::from_generators(&generators(), 3).unwrap();
+
+        let values: Vec<_> = (0..3).map(|_| ScalarField::rand(&mut rng)).collect();
+        let (commitment, opening) = params.commit_random(&values, &mut rng).unwrap();
+        assert!(params.verify_opening(&commitment, &opening));
+
+        let mut wrong = opening.clone();
+        wrong.values[1] += ScalarField::from(1u64);
+        assert!(!params.verify_opening(&commitment, &wrong));
+
+        let mut wrong = opening;
+        wrong.randomness += ScalarField::from(1u64);
+        assert!(!params.verify_opening(&commitment, &wrong));
+
+        // Too many values for the bases
+        assert!(params.commit(&[ScalarField::from(0u64); 4], &ScalarField::from(0u64)).is_err());
+    }
+
+    #[test]
+    fn test_homomorphic_add() {
+        let mut rng = thread_rng();
+        let params = G1PedersenParams::from_generators(&generators(), 3).unwrap();
+
+        let a: Vec<_> = (0..3).map(|_| ScalarField::rand(&mut rng)).collect();
+        let b: Vec<_> = (0..2).map(|_| ScalarField::rand(&mut rng)).collect();
+        let (ca, oa) = params.commit_random(&a, &mut rng).unwrap();
+        let (cb, ob) = params.commit_random(&b, &mut rng).unwrap();
+
+        let sum = ca.add(&cb);
+        let sum_opening = oa.add(&ob);
+        assert_eq!(sum_opening.values[2], a[2]);
+        assert!(params.verify_opening(&sum, &sum_opening));
+    }
+
+    #[test]
+    fn test_rerandomize() {
+        let mut rng = thread_rng();
+        let params = G2PedersenParams::from_generators(&generators(), 2).unwrap();
+
+        let values = vec![ScalarField::from(5u64), ScalarField::from(7u64)];
+        let (commitment, opening) = params.commit_random(&values, &mut rng).unwrap();
+        let (rerandomized, new_opening) = params.rerandomize(&commitment, &opening, &mut rng);
+
+        assert_ne!(rerandomized, commitment);
+        assert_eq!(new_opening.values, opening.values);
+        assert!(params.verify_opening(&rerandomized, &new_opening));
+        assert!(!params.verify_opening(&rerandomized, &opening));
+    }
+
+    #[test]
+    fn test_g2_commitment_to_secret_key() {
+        let mut rng = thread_rng();
+        let generators = generators();
+        let params = G2PedersenParams::from_generators(&generators, 1).unwrap();
+        let (sk, pk) = keygen(&mut rng);
+
+        // pk* = G^sk · H^r = pk + r · H
+        let r = ScalarField::rand(&mut rng);
+        let pk_star = params.commit(&[sk.sk], &r).unwrap();
+        let expected = (G2Projective::from(pk.pk) + generators.g2_generators[0] * r).into_affine();
+        assert_eq!(pk_star.point, expected);
+    }
+
+    #[test]
+    fn test_not_enough_generators() {
+        let generators = generators();
+        assert!(G1PedersenParams::from_generators(&generators, 4).is_err());
+        assert!(G2PedersenParams::from_generators(&generators, 4).is_err());
+        assert!(G2PedersenParams::from_generators(&generators, 3).is_ok());
+    }
+}
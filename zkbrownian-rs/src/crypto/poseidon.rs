@@ -4,64 +4,341 @@
 //! - Deriving θ = Hash(φ_ν, sid, pid, ν)
 //! - Hashing public keys
 //! - General-purpose ZK-friendly hashing
+//!
+//! The permutation follows the HADES design from the Poseidon paper
+//! (<https://eprint.iacr.org/2019/458>): R_F/2 full rounds, R_P partial
+//! rounds, R_F/2 full rounds, with round constants and the MDS matrix
+//! produced by the reference Grain LFSR.
 
+use crate::crypto::encoding::G1Encoding;
 use crate::types::{PacketId, ScalarField, SessionId};
+use ark_crypto_primitives::sponge::poseidon::find_poseidon_ark_and_mds;
+use ark_ff::{Field, PrimeField};
+
+/// Number of full rounds (R_F) for t = 3, α = 5 over a 255-bit field at
+/// 128-bit security
+pub const POSEIDON_FULL_ROUNDS: usize = 8;
+
+/// Number of partial rounds (R_P) for t = 3, α = 5 over a 255-bit field at
+/// 128-bit security
+pub const POSEIDON_PARTIAL_ROUNDS: usize = 57;
+
+/// S-box exponent (x ↦ x^α), valid since gcd(5, r - 1) = 1 for BLS12-381 Fr
+pub const POSEIDON_ALPHA: u64 = 5;
 
-/// Poseidon hash configuration (stub)
+/// Sponge rate (number of field elements absorbed per permutation)
+pub const POSEIDON_RATE: usize = 2;
+
+/// Sponge capacity
+pub const POSEIDON_CAPACITY: usize = 1;
+
+/// Poseidon hash configuration
+#[derive(Clone, Debug)]
 pub struct PoseidonConfig {
-    // Round constants, MDS matrix, etc.
-    // TODO: Implement proper Poseidon parameters for BLS12-381
+    /// Number of full rounds (R_F), split evenly before and after the partial rounds
+    pub full_rounds: usize,
+    /// Number of partial rounds (R_P)
+    pub partial_rounds: usize,
+    /// S-box exponent α
+    pub alpha: u64,
+    /// Sponge rate
+    pub rate: usize,
+    /// Sponge capacity
+    pub capacity: usize,
+    /// Round constants, one row of `rate + capacity` elements per round
+    pub ark: Vec<Vec<ScalarField>>,
+    /// MDS matrix of size `(rate + capacity) × (rate + capacity)`
+    pub mds: Vec<Vec<ScalarField>>,
+}
+
+impl PoseidonConfig {
+    /// Generate round constants and MDS matrix with the Grain LFSR
+    ///
+    /// The Grain LFSR as specified in the Poseidon reference implementation
+    /// only supports a capacity of one field element.
+    pub fn from_grain_lfsr(
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: u64,
+        rate: usize,
+    ) -> Self {
+        let (ark, mds) = find_poseidon_ark_and_mds::<ScalarField>(
+            ScalarField::MODULUS_BIT_SIZE as u64,
+            rate,
+            full_rounds as u64,
+            partial_rounds as u64,
+            0,
+        );
+
+        Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            rate,
+            capacity: 1,
+            ark,
+            mds,
+        }
+    }
+
+    /// Width t of the permutation state
+    pub fn width(&self) -> usize {
+        self.rate + self.capacity
+    }
+
+    /// Apply the Poseidon permutation to `state` in place
+    pub fn permute(&self, state: &mut [ScalarField]) {
+        assert_eq!(state.len(), self.width(), "Poseidon state has wrong width");
+
+        let half_full = self.full_rounds / 2;
+        let mut round = 0;
+
+        for _ in 0..half_full {
+            self.full_round(state, round);
+            round += 1;
+        }
+        for _ in 0..self.partial_rounds {
+            self.partial_round(state, round);
+            round += 1;
+        }
+        for _ in 0..(self.full_rounds - half_full) {
+            self.full_round(state, round);
+            round += 1;
+        }
+    }
+
+    fn full_round(&self, state: &mut [ScalarField], round: usize) {
+        self.apply_ark(state, round);
+        for elem in state.iter_mut() {
+            *elem = elem.pow([self.alpha]);
+        }
+        self.apply_mds(state);
+    }
+
+    fn partial_round(&self, state: &mut [ScalarField], round: usize) {
+        self.apply_ark(state, round);
+        state[0] = state[0].pow([self.alpha]);
+        self.apply_mds(state);
+    }
+
+    fn apply_ark(&self, state: &mut [ScalarField], round: usize) {
+        for (elem, c) in state.iter_mut().zip(self.ark[round].iter()) {
+            *elem += c;
+        }
+    }
+
+    fn apply_mds(&self, state: &mut [ScalarField]) {
+        let new_state: Vec<ScalarField> = self
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(state.iter())
+                    .fold(ScalarField::from(0u64), |acc, (m, s)| acc + *m * s)
+            })
+            .collect();
+        state.copy_from_slice(&new_state);
+    }
 }
 
 impl Default for PoseidonConfig {
     fn default() -> Self {
-        Self {}
+        Self::from_grain_lfsr(
+            POSEIDON_FULL_ROUNDS,
+            POSEIDON_PARTIAL_ROUNDS,
+            POSEIDON_ALPHA,
+            POSEIDON_RATE,
+        )
     }
 }
 
-/// Poseidon hasher
-pub struct PoseidonHash {
-    _config: PoseidonConfig,
+/// Domain-separation tags for every use of Poseidon in the protocol
+///
+/// The tag initialises the capacity element of the sponge, so outputs
+/// computed for different purposes live in independent hash functions: a θ
+/// value can never collide with a Merkle node or a leaf commitment. New
+/// uses of Poseidon must get a new variant here rather than reuse one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DomainTag {
+    /// General-purpose hashing through [`PoseidonHash::hash`]
+    Generic,
+    /// θ = Hash(φ_ν, sid, pid, ν)
+    Theta,
+    /// Merkle tree leaves
+    MerkleLeaf,
+    /// Leaves of a node's neighbour tree
+    NeighbourLeaf,
+    /// Merkle tree inner nodes
+    MerkleNode,
+    /// Hiding commitments Hash(leaf, r) to Merkle leaves
+    LeafCommitment,
+    /// Public key hashing
+    PublicKey,
+    /// Routing value ρ extracted from a PRF output φ
+    RoutingValue,
+    /// Fiat–Shamir transcripts of the proof components
+    Transcript,
 }
 
-impl PoseidonHash {
-    pub fn new() -> Self {
+impl DomainTag {
+    /// Byte label of the tag (at most 31 bytes, so it fits in one field element)
+    pub fn label(&self) -> &'static [u8] {
+        match self {
+            DomainTag::Generic => b"zkbrownian/generic",
+            DomainTag::Theta => b"zkbrownian/theta",
+            DomainTag::MerkleLeaf => b"zkbrownian/merkle-leaf",
+            DomainTag::NeighbourLeaf => b"zkbrownian/neighbour-leaf",
+            DomainTag::MerkleNode => b"zkbrownian/merkle-node",
+            DomainTag::LeafCommitment => b"zkbrownian/leaf-commitment",
+            DomainTag::PublicKey => b"zkbrownian/public-key",
+            DomainTag::RoutingValue => b"zkbrownian/routing-value",
+            DomainTag::Transcript => b"zkbrownian/transcript",
+        }
+    }
+
+    /// Field element placed in the sponge capacity
+    pub fn to_field(&self) -> ScalarField {
+        ScalarField::from_le_bytes_mod_order(self.label())
+    }
+}
+
+/// Whether a sponge is currently absorbing or squeezing
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SpongeMode {
+    Absorbing,
+    Squeezing,
+}
+
+/// Duplex sponge over the Poseidon permutation
+///
+/// Inputs are added into the `rate` part of the state and the permutation is
+/// applied whenever a block is full. Switching from absorbing to squeezing
+/// appends a single `1` followed by implicit zeros (10* padding), so
+/// absorbed sequences of different lengths never produce the same output.
+#[derive(Clone, Debug)]
+pub struct PoseidonSponge<'a> {
+    config: &'a PoseidonConfig,
+    state: Vec<ScalarField>,
+    position: usize,
+    mode: SpongeMode,
+}
+
+impl<'a> PoseidonSponge<'a> {
+    /// Create a fresh sponge for the given domain
+    pub fn new(config: &'a PoseidonConfig, tag: DomainTag) -> Self {
+        let mut state = vec![ScalarField::from(0u64); config.width()];
+        state[0] = tag.to_field();
+
         Self {
-            _config: PoseidonConfig::default(),
+            config,
+            state,
+            position: 0,
+            mode: SpongeMode::Absorbing,
         }
     }
 
-    /// Hash arbitrary field elements
-    pub fn hash(&self, inputs: &[ScalarField]) -> ScalarField {
-        // TODO: Implement actual Poseidon hash
-        // For now, this is a placeholder
-        if inputs.is_empty() {
-            return ScalarField::from(0u64);
+    /// Absorb field elements into the sponge
+    pub fn absorb(&mut self, inputs: &[ScalarField]) {
+        if self.mode == SpongeMode::Squeezing {
+            self.mode = SpongeMode::Absorbing;
+            self.position = 0;
         }
 
-        // Simple placeholder: sum inputs (NOT secure, just for structure)
-        let mut result = ScalarField::from(0u64);
         for input in inputs {
-            result += input;
+            if self.position == self.config.rate {
+                self.config.permute(&mut self.state);
+                self.position = 0;
+            }
+            self.state[self.config.capacity + self.position] += input;
+            self.position += 1;
+        }
+    }
+
+    /// Squeeze `count` field elements out of the sponge
+    pub fn squeeze(&mut self, count: usize) -> Vec<ScalarField> {
+        if self.mode == SpongeMode::Absorbing {
+            if self.position == self.config.rate {
+                self.config.permute(&mut self.state);
+                self.position = 0;
+            }
+            self.state[self.config.capacity + self.position] += ScalarField::from(1u64);
+            self.config.permute(&mut self.state);
+            self.position = 0;
+            self.mode = SpongeMode::Squeezing;
+        }
+
+        let mut outputs = Vec::with_capacity(count);
+        for _ in 0..count {
+            if self.position == self.config.rate {
+                self.config.permute(&mut self.state);
+                self.position = 0;
+            }
+            outputs.push(self.state[self.config.capacity + self.position]);
+            self.position += 1;
         }
-        result
+        outputs
+    }
+
+    /// Squeeze a single field element
+    pub fn squeeze_one(&mut self) -> ScalarField {
+        self.squeeze(1)[0]
+    }
+}
+
+/// Poseidon hasher
+pub struct PoseidonHash {
+    config: PoseidonConfig,
+}
+
+impl PoseidonHash {
+    pub fn new() -> Self {
+        Self::with_config(PoseidonConfig::default())
+    }
+
+    /// Create a hasher with custom parameters
+    pub fn with_config(config: PoseidonConfig) -> Self {
+        Self { config }
+    }
+
+    /// Get the underlying Poseidon parameters
+    pub fn config(&self) -> &PoseidonConfig {
+        &self.config
+    }
+
+    /// Start a sponge for the given domain
+    pub fn sponge(&self, tag: DomainTag) -> PoseidonSponge<'_> {
+        PoseidonSponge::new(&self.config, tag)
+    }
+
+    /// Hash arbitrary field elements in the generic domain
+    pub fn hash(&self, inputs: &[ScalarField]) -> ScalarField {
+        self.hash_with_domain(DomainTag::Generic, inputs)
+    }
+
+    /// Hash arbitrary field elements in a specific domain
+    pub fn hash_with_domain(&self, tag: DomainTag, inputs: &[ScalarField]) -> ScalarField {
+        let mut sponge = self.sponge(tag);
+        sponge.absorb(inputs);
+        sponge.squeeze_one()
     }
 
     /// Hash theta derivation: θ = Hash(φ_ν, sid, pid, ν)
+    ///
+    /// φ_ν is given by its canonical encoding (see [`crate::crypto::encoding`]).
     pub fn hash_theta(
         &self,
-        phi_prev: &ScalarField,
+        phi_prev: &G1Encoding,
         sid: SessionId,
         pid: PacketId,
         nu: usize,
     ) -> ScalarField {
-        let inputs = vec![
-            *phi_prev,
+        let mut inputs = phi_prev.to_vec();
+        inputs.extend([
             ScalarField::from(sid),
             ScalarField::from(pid as u64),
             ScalarField::from(nu as u64),
-        ];
-        self.hash(&inputs)
+        ]);
+        self.hash_with_domain(DomainTag::Theta, &inputs)
     }
 }
 
@@ -85,9 +362,166 @@ mod tests {
 
     #[test]
     fn test_hash_theta() {
+        use crate::crypto::encoding::encode_g1;
+        use crate::types::G1Point;
+        use ark_ec::AffineRepr;
+
         let hasher = PoseidonHash::new();
-        let phi_prev = ScalarField::from(42u64);
+        let phi_prev = encode_g1(&G1Point::generator());
         let theta = hasher.hash_theta(&phi_prev, 100, 5, 3);
         assert_ne!(theta, ScalarField::from(0u64));
+
+        // θ chains over φ: a different previous output yields a different θ
+        let phi_zero = encode_g1(&G1Point::zero());
+        assert_ne!(theta, hasher.hash_theta(&phi_zero, 100, 5, 3));
+    }
+
+    #[test]
+    fn test_config_dimensions() {
+        let config = PoseidonConfig::default();
+        assert_eq!(config.width(), POSEIDON_RATE + POSEIDON_CAPACITY);
+        assert_eq!(config.ark.len(), config.full_rounds + config.partial_rounds);
+        assert!(config.ark.iter().all(|row| row.len() == config.width()));
+        assert_eq!(config.mds.len(), config.width());
+        assert!(config.mds.iter().all(|row| row.len() == config.width()));
+    }
+
+    /// The permutation and the sponge agree with ark-crypto-primitives under
+    /// the same round constants and MDS matrix
+    #[test]
+    fn test_matches_reference_sponge() {
+        use ark_crypto_primitives::sponge::{
+            poseidon::{PoseidonConfig as ReferenceConfig, PoseidonSponge as ReferenceSponge},
+            CryptographicSponge,
+        };
+
+        let hasher = PoseidonHash::new();
+        let config = hasher.config();
+        let reference_config = ReferenceConfig::new(
+            config.full_rounds,
+            config.partial_rounds,
+            config.alpha,
+            config.mds.clone(),
+            config.ark.clone(),
+            config.rate,
+            config.capacity,
+        );
+
+        // One permutation: the reference permutes its state before the
+        // first squeeze
+        let mut state: Vec<ScalarField> = (1..=3u64).map(ScalarField::from).collect();
+        let mut reference = ReferenceSponge::new(&reference_config);
+        reference.state = state.clone();
+        reference.squeeze_field_elements::<ScalarField>(1);
+        config.permute(&mut state);
+        assert_eq!(state, reference.state);
+
+        // One hash: the reference sponge has no domain tag or padding of its
+        // own, so the tag goes into its capacity and the padding is absorbed
+        let inputs: Vec<ScalarField> = (4..7u64).map(ScalarField::from).collect();
+        let mut reference = ReferenceSponge::new(&reference_config);
+        reference.state[0] = DomainTag::Theta.to_field();
+        let mut padded = inputs.clone();
+        padded.push(ScalarField::from(1u64));
+        reference.absorb(&padded);
+        assert_eq!(
+            hasher.hash_with_domain(DomainTag::Theta, &inputs),
+            reference.squeeze_field_elements::<ScalarField>(1)[0]
+        );
+    }
+
+    #[test]
+    fn test_poseidon_deterministic() {
+        let hasher = PoseidonHash::new();
+        let input = vec![ScalarField::from(7u64), ScalarField::from(8u64), ScalarField::from(9u64)];
+        assert_eq!(hasher.hash(&input), hasher.hash(&input));
+    }
+
+    #[test]
+    fn test_poseidon_input_sensitivity() {
+        let hasher = PoseidonHash::new();
+        let a = hasher.hash(&[ScalarField::from(1u64), ScalarField::from(2u64)]);
+        let b = hasher.hash(&[ScalarField::from(2u64), ScalarField::from(1u64)]);
+        let c = hasher.hash(&[ScalarField::from(1u64), ScalarField::from(2u64), ScalarField::from(0u64)]);
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+
+        // The old placeholder summed its inputs; the permutation must not be additive
+        assert_ne!(a, hasher.hash(&[ScalarField::from(3u64)]));
+    }
+
+    #[test]
+    fn test_domain_separation() {
+        let hasher = PoseidonHash::new();
+        let inputs = [ScalarField::from(5u64), ScalarField::from(6u64)];
+
+        let tags = [
+            DomainTag::Generic,
+            DomainTag::Theta,
+            DomainTag::MerkleLeaf,
+            DomainTag::NeighbourLeaf,
+            DomainTag::MerkleNode,
+            DomainTag::LeafCommitment,
+            DomainTag::PublicKey,
+            DomainTag::RoutingValue,
+            DomainTag::Transcript,
+        ];
+        let outputs: Vec<ScalarField> = tags
+            .iter()
+            .map(|tag| hasher.hash_with_domain(*tag, &inputs))
+            .collect();
+
+        for i in 0..outputs.len() {
+            for j in (i + 1)..outputs.len() {
+                assert_ne!(tags[i].to_field(), tags[j].to_field());
+                assert_ne!(outputs[i], outputs[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sponge_padding() {
+        let hasher = PoseidonHash::new();
+        let zero = ScalarField::from(0u64);
+
+        // Trailing zeros and full blocks must not collide with shorter inputs
+        let a = hasher.hash(&[]);
+        let b = hasher.hash(&[zero]);
+        let c = hasher.hash(&[zero, zero]);
+        let d = hasher.hash(&[zero, zero, zero]);
+        assert_ne!(a, b);
+        assert_ne!(b, c);
+        assert_ne!(c, d);
+    }
+
+    #[test]
+    fn test_sponge_streaming_absorb() {
+        let hasher = PoseidonHash::new();
+        let inputs: Vec<ScalarField> = (0..5u64).map(ScalarField::from).collect();
+
+        let mut sponge = hasher.sponge(DomainTag::Generic);
+        sponge.absorb(&inputs[..2]);
+        sponge.absorb(&inputs[2..]);
+        assert_eq!(sponge.squeeze_one(), hasher.hash(&inputs));
+    }
+
+    #[test]
+    fn test_sponge_duplex() {
+        let hasher = PoseidonHash::new();
+
+        let mut sponge = hasher.sponge(DomainTag::Generic);
+        sponge.absorb(&[ScalarField::from(1u64)]);
+        let out = sponge.squeeze(3);
+        assert_eq!(out.len(), 3);
+        assert_ne!(out[0], out[1]);
+        assert_ne!(out[1], out[2]);
+
+        // Absorbing after squeezing changes subsequent outputs
+        let mut other = hasher.sponge(DomainTag::Generic);
+        other.absorb(&[ScalarField::from(1u64)]);
+        other.squeeze(3);
+        sponge.absorb(&[ScalarField::from(2u64)]);
+        other.absorb(&[ScalarField::from(3u64)]);
+        assert_ne!(sponge.squeeze_one(), other.squeeze_one());
     }
 }
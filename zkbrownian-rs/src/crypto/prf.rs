@@ -1,11 +1,25 @@
 //! PRF (Pseudorandom Function) operations
 //!
 //! Implements φ_{ν+1} = G^{1/(θ+sk)}
+//!
+//! This is the Dodis–Yampolskiy VRF, so φ is publicly verifiable against the
+//! forwarder's key with a single pairing equation.
 
 use crate::crypto::curve_ops::compute_prf_exponent;
-use crate::types::{G1Point, PrfOutput, ScalarField, SecretKey};
-use ark_bls12_381::G1Projective;
-use ark_ec::{AffineRepr, CurveGroup};
+use crate::crypto::encoding::encode_g1;
+use crate::crypto::poseidon::{DomainTag, PoseidonHash};
+use crate::types::{
+    DiversifiedPublicKey, G1Point, G2Point, PairingEngine, PrfOutput, PublicKey, ScalarField,
+    SecretKey,
+};
+use ark_bls12_381::{G1Projective, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField};
+use ark_std::{cfg_iter, Zero};
+use rand::Rng;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 #[cfg(test)]
 use ark_ec::PrimeGroup;
@@ -30,36 +44,191 @@ pub fn compute_prf(theta: &ScalarField, sk: &SecretKey, generator: &G1Point) ->
     Some(PrfOutput { phi: phi_point })
 }
 
-/// Extract first 32 bits from PRF output for routing selection
+/// Verify a PRF output against a public key: e(φ, G2^θ · pk) = e(G1, G2)
+///
+/// Holds iff φ = G1^{1/(θ+sk)} for pk = G2^sk. If θ + sk = 0 no φ
+/// satisfies the equation, matching [`compute_prf`] returning `None`.
+pub fn verify_prf(phi: &PrfOutput, theta: &ScalarField, pk: &PublicKey) -> bool {
+    let shifted_pk = (G2Projective::from(pk.pk) + G2Point::generator() * theta).into_affine();
+
+    PairingEngine::multi_pairing(
+        [phi.phi, -G1Point::generator()],
+        [shifted_pk, G2Point::generator()],
+    )
+    .is_zero()
+}
+
+/// Verify a PRF output against a diversified public key
+///
+/// With ppk = (pk^d, G2^d) the check becomes
+/// e(φ, ppk_2^θ · ppk_1) = e(G1, ppk_2), since both sides equal
+/// e(G1, G2)^d. The relation degenerates for d = 0 (every φ passes), so a
+/// diversified key with ppk_2 at infinity is rejected outright.
+pub fn verify_prf_diversified(
+    phi: &PrfOutput,
+    theta: &ScalarField,
+    ppk: &DiversifiedPublicKey,
+) -> bool {
+    if ppk.ppk_2.is_zero() {
+        return false;
+    }
+
+    let shifted_ppk = (G2Projective::from(ppk.ppk_1) + ppk.ppk_2 * theta).into_affine();
+
+    PairingEngine::multi_pairing(
+        [phi.phi, -G1Point::generator()],
+        [shifted_ppk, ppk.ppk_2],
+    )
+    .is_zero()
+}
+
+/// Sample a 128-bit weight for a random linear combination of equations
+fn batch_weight<R: Rng>(rng: &mut R) -> ScalarField {
+    ScalarField::from(rng.gen::<u128>())
+}
+
+/// Run a single multi-Miller loop and final exponentiation, returning
+/// whether the product of pairings is the identity
+fn pairing_product_is_one(
+    g1: Vec<<PairingEngine as Pairing>::G1Prepared>,
+    g2: Vec<<PairingEngine as Pairing>::G2Prepared>,
+) -> bool {
+    let miller = PairingEngine::multi_miller_loop(g1, g2);
+    PairingEngine::final_exponentiation(miller).is_some_and(|out| out.is_zero())
+}
+
+/// Batch-verify PRF outputs against public keys
+///
+/// Each triple (φ_i, θ_i, pk_i) is checked as in [`verify_prf`]. With random
+/// 128-bit weights r_i the equations are folded into
+///
+/// ```text
+/// Π_i e(φ_i^{r_i}, G2^{θ_i} · pk_i) · e(G1^{-Σ r_i}, G2) = 1
+/// ```
+///
+/// which costs one multi-Miller loop over n + 1 pairs and a single final
+/// exponentiation. A batch containing an invalid triple passes with
+/// probability at most 2^-128. On failure every triple is checked on its own
+/// and the index of the first invalid one is returned, or `instances.len()`
+/// if each of them passes on its own.
+pub fn batch_verify_prf<R: Rng>(
+    instances: &[(&PrfOutput, ScalarField, &PublicKey)],
+    rng: &mut R,
+) -> Result<(), usize> {
+    if instances.is_empty() {
+        return Ok(());
+    }
+
+    let weights: Vec<ScalarField> = (0..instances.len()).map(|_| batch_weight(rng)).collect();
+
+    let (mut g1, mut g2): (Vec<_>, Vec<_>) = cfg_iter!(instances)
+        .zip(cfg_iter!(weights))
+        .map(|((phi, theta, pk), r)| {
+            let shifted_pk = (G2Projective::from(pk.pk) + G2Point::generator() * theta).into_affine();
+            (
+                <PairingEngine as Pairing>::G1Prepared::from((phi.phi * r).into_affine()),
+                <PairingEngine as Pairing>::G2Prepared::from(shifted_pk),
+            )
+        })
+        .unzip();
+
+    let weight_sum: ScalarField = weights.iter().sum();
+    g1.push((-(G1Point::generator() * weight_sum)).into_affine().into());
+    g2.push(G2Point::generator().into());
+
+    if pairing_product_is_one(g1, g2) {
+        return Ok(());
+    }
+
+    Err(instances
+        .iter()
+        .position(|(phi, theta, pk)| !verify_prf(phi, theta, pk))
+        .unwrap_or(instances.len()))
+}
+
+/// Batch-verify PRF outputs against diversified public keys
+///
+/// Same as [`batch_verify_prf`] for the check of [`verify_prf_diversified`].
+/// The right-hand side e(G1, ppk_2) differs per key, so every triple
+/// contributes two pairs to the Miller loop, but the final exponentiation is
+/// still shared by the whole batch.
+pub fn batch_verify_prf_diversified<R: Rng>(
+    instances: &[(&PrfOutput, ScalarField, &DiversifiedPublicKey)],
+    rng: &mut R,
+) -> Result<(), usize> {
+    if let Some(i) = instances.iter().position(|(_, _, ppk)| ppk.ppk_2.is_zero()) {
+        return Err(i);
+    }
+    if instances.is_empty() {
+        return Ok(());
+    }
+
+    let weights: Vec<ScalarField> = (0..instances.len()).map(|_| batch_weight(rng)).collect();
+
+    let pairs: Vec<_> = cfg_iter!(instances)
+        .zip(cfg_iter!(weights))
+        .map(|((phi, theta, ppk), r)| {
+            let shifted_ppk = (G2Projective::from(ppk.ppk_1) + ppk.ppk_2 * theta).into_affine();
+            [
+                (
+                    <PairingEngine as Pairing>::G1Prepared::from((phi.phi * r).into_affine()),
+                    <PairingEngine as Pairing>::G2Prepared::from(shifted_ppk),
+                ),
+                (
+                    <PairingEngine as Pairing>::G1Prepared::from(
+                        (-(G1Point::generator() * r)).into_affine(),
+                    ),
+                    <PairingEngine as Pairing>::G2Prepared::from(ppk.ppk_2),
+                ),
+            ]
+        })
+        .collect();
+    let (g1, g2): (Vec<_>, Vec<_>) = pairs.into_iter().flatten().unzip();
+
+    if pairing_product_is_one(g1, g2) {
+        return Ok(());
+    }
+
+    Err(instances
+        .iter()
+        .position(|(phi, theta, ppk)| !verify_prf_diversified(phi, theta, ppk))
+        .unwrap_or(instances.len()))
+}
+
+/// Number of bits in a routing value ρ
+pub const ROUTING_VALUE_BITS: usize = 32;
+
+/// Extract the 32-bit routing value ρ from a PRF output
 ///
 /// This converts the PRF output φ (a G1 point) to a 32-bit value ρ
-/// which is then used with the weight matrix to select the next hop
+/// which is then used with the weight matrix to select the next hop.
+///
+/// # Specification
+/// ```text
+/// digest = Poseidon_{RoutingValue}(encode_g1(φ))
+/// ρ      = digest mod 2^32   (low 32 bits of the canonical integer representative)
+/// ```
+/// `encode_g1` is the canonical injective encoding from
+/// [`crate::crypto::encoding`], so ρ does not depend on any formatting or
+/// serialization code. The digest is uniform over Fr, and since r > 2^254 the
+/// low 32 bits are within statistical distance 2^32 / r < 2^-222 of uniform
+/// over [0, 2^32). The same definition is reproduced in-circuit by
+/// `crypto::constraints::extract_routing_value_gadget`.
 pub fn extract_routing_value(phi: &PrfOutput) -> u32 {
-    // Convert the x-coordinate of the point to bytes
-    // Take first 4 bytes as u32
-    let x_coord = phi.phi.x();
-
-    // Serialize the x-coordinate
-    // For now, use a simple hash of the serialized bytes
-    // TODO: Implement proper deterministic extraction
-    let bytes = x_coord
-        .expect("Point should have x-coordinate")
-        .to_string()
-        .as_bytes()
-        .to_vec();
-
-    if bytes.len() >= 4 {
-        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
-    } else {
-        // Fallback if serialization is too short
-        0
-    }
+    extract_routing_value_with(&PoseidonHash::new(), phi)
+}
+
+/// Extract the routing value ρ using an existing hasher
+pub fn extract_routing_value_with(hasher: &PoseidonHash, phi: &PrfOutput) -> u32 {
+    let digest = hasher.hash_with_domain(DomainTag::RoutingValue, &encode_g1(&phi.phi));
+    let bytes = digest.into_bigint().to_bytes_le();
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::curve_ops::{diversify, keygen};
     use rand::thread_rng;
 
     #[test]
@@ -74,6 +243,109 @@ mod tests {
         assert!(phi.is_some());
     }
 
+    #[test]
+    fn test_verify_prf() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+        let (_other_sk, other_pk) = keygen(&mut rng);
+
+        let theta = ScalarField::from(42u64);
+        let generator = G1Projective::generator().into_affine();
+        let phi = compute_prf(&theta, &sk, &generator).unwrap();
+
+        assert!(verify_prf(&phi, &theta, &pk));
+        assert!(!verify_prf(&phi, &ScalarField::from(43u64), &pk));
+        assert!(!verify_prf(&phi, &theta, &other_pk));
+        assert!(!verify_prf(&PrfOutput { phi: G1Point::zero() }, &theta, &pk));
+    }
+
+    #[test]
+    fn test_verify_prf_diversified() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+        let (_other_sk, other_pk) = keygen(&mut rng);
+        let (ppk, _d) = diversify(&pk, &mut rng);
+        let (other_ppk, _d) = diversify(&other_pk, &mut rng);
+
+        let theta = ScalarField::from(7u64);
+        let generator = G1Projective::generator().into_affine();
+        let phi = compute_prf(&theta, &sk, &generator).unwrap();
+
+        assert!(verify_prf_diversified(&phi, &theta, &ppk));
+        assert!(!verify_prf_diversified(&phi, &ScalarField::from(8u64), &ppk));
+        assert!(!verify_prf_diversified(&phi, &theta, &other_ppk));
+
+        // d = 0 would make any φ pass
+        let degenerate = DiversifiedPublicKey {
+            ppk_1: G2Point::zero(),
+            ppk_2: G2Point::zero(),
+        };
+        assert!(!verify_prf_diversified(&phi, &theta, &degenerate));
+    }
+
+    #[test]
+    fn test_batch_verify_prf() {
+        let mut rng = thread_rng();
+        let generator = G1Projective::generator().into_affine();
+
+        let keys: Vec<_> = (0..8).map(|_| keygen(&mut rng)).collect();
+        let thetas: Vec<_> = (0..8u64).map(ScalarField::from).collect();
+        let mut outputs: Vec<_> = keys
+            .iter()
+            .zip(&thetas)
+            .map(|((sk, _pk), theta)| compute_prf(theta, sk, &generator).unwrap())
+            .collect();
+
+        let batch = |outputs: &[PrfOutput], rng: &mut _| {
+            let instances: Vec<_> = outputs
+                .iter()
+                .zip(&thetas)
+                .zip(&keys)
+                .map(|((phi, theta), (_sk, pk))| (phi, *theta, pk))
+                .collect();
+            batch_verify_prf(&instances, rng)
+        };
+
+        assert_eq!(batch(&outputs, &mut rng), Ok(()));
+        assert_eq!(batch_verify_prf(&[], &mut rng), Ok(()));
+
+        outputs[5] = outputs[4].clone();
+        assert_eq!(batch(&outputs, &mut rng), Err(5));
+    }
+
+    #[test]
+    fn test_batch_verify_prf_diversified() {
+        let mut rng = thread_rng();
+        let generator = G1Projective::generator().into_affine();
+
+        let keys: Vec<_> = (0..6).map(|_| keygen(&mut rng)).collect();
+        let ppks: Vec<_> = keys.iter().map(|(_, pk)| diversify(pk, &mut rng).0).collect();
+        let theta = ScalarField::from(99u64);
+        let outputs: Vec<_> = keys
+            .iter()
+            .map(|(sk, _)| compute_prf(&theta, sk, &generator).unwrap())
+            .collect();
+
+        let degenerate = DiversifiedPublicKey {
+            ppk_1: G2Point::zero(),
+            ppk_2: G2Point::zero(),
+        };
+
+        let mut instances: Vec<_> = outputs
+            .iter()
+            .zip(&ppks)
+            .map(|(phi, ppk)| (phi, theta, ppk))
+            .collect();
+        assert_eq!(batch_verify_prf_diversified(&instances, &mut rng), Ok(()));
+
+        instances[2].1 = ScalarField::from(100u64);
+        assert_eq!(batch_verify_prf_diversified(&instances, &mut rng), Err(2));
+
+        instances[2].1 = theta;
+        instances[4].2 = &degenerate;
+        assert_eq!(batch_verify_prf_diversified(&instances, &mut rng), Err(4));
+    }
+
     #[test]
     fn test_extract_routing_value() {
         let mut rng = thread_rng();
@@ -85,8 +357,29 @@ mod tests {
         let phi = compute_prf(&theta, &sk, &generator).unwrap();
         let rho = extract_routing_value(&phi);
 
-        // Just check it produces a value (any value for now)
-        println!("Routing value ρ: {}", rho);
+        // ρ is the low 32 bits of the Poseidon digest of the encoded φ
+        let digest = PoseidonHash::new().hash_with_domain(DomainTag::RoutingValue, &encode_g1(&phi.phi));
+        let low_bits = digest.into_bigint().as_ref()[0] & 0xffff_ffff;
+        assert_eq!(rho as u64, low_bits);
+        assert_eq!(rho, extract_routing_value(&phi));
+    }
+
+    #[test]
+    fn test_routing_value_spread() {
+        let mut rng = thread_rng();
+        let (sk, _pk) = keygen(&mut rng);
+        let generator = G1Projective::generator().into_affine();
+        let hasher = PoseidonHash::new();
+
+        // Over 64 samples the top bit should not be stuck, unlike ASCII digits
+        let mut high = 0;
+        for i in 0..64u64 {
+            let phi = compute_prf(&ScalarField::from(i), &sk, &generator).unwrap();
+            if extract_routing_value_with(&hasher, &phi) >= 1u32 << 31 {
+                high += 1;
+            }
+        }
+        assert!(high > 8 && high < 56);
     }
 
     #[test]
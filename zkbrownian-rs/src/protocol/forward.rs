@@ -2,13 +2,19 @@
 //!
 //! Core forwarding logic: Forward(pk_ν, sk_ν, m) -> (m', k_R, d)
 
-use crate::crypto::{compute_prf, diversify_with_diversifier, extract_routing_value, PoseidonHash};
+use crate::crypto::{
+    compute_prf, cumulative_weights, diversify_with_diversifier, encode_g1,
+    extract_routing_value_with, protocol_generators, MerkleTree, PoseidonHash,
+};
 use crate::protocol::routing::{select_next_hop, WeightMatrix};
+use crate::proving::bridging::{prove_bridging, BridgingStatement, BridgingWitness};
 use crate::proving::circuits::ForwardCircuit;
+use crate::proving::pubkey_ops::{prove_public_key_ops, PublicKeyOpsStatement, PublicKeyOpsWitness};
+use ark_serialize::CanonicalSerialize;
 use crate::types::*;
 use crate::MAX_HOPS;
 use ark_bls12_381::G1Projective;
-use ark_ec::{CurveGroup, PrimeGroup};
+use ark_ec::{AffineRepr, CurveGroup, PrimeGroup};
 use ark_std::UniformRand;
 use rand::Rng;
 
@@ -21,7 +27,7 @@ use rand::Rng;
 /// 1. Check hop count ν ≤ ν_max
 /// 2. Derive θ ← Hash(φ_ν, sid, pid, ν)
 /// 3. Compute φ_{ν+1} ← G^{1/(θ+sk)}
-/// 4. Select next hop using ρ_{ν+1} ← First32Bits(φ_{ν+1})
+/// 4. Select next hop using ρ_{ν+1} ← First32Bits(Hash(φ_{ν+1}))
 /// 5. Create diversified public key ppk_{ν+1}
 /// 6. Generate proof π_{ν+1}
 /// 7. Return updated message m'
@@ -53,16 +59,10 @@ pub fn forward<R: Rng>(
 
     // Step 2: Derive θ = Hash(φ_ν, sid, pid, ν)
     let hasher = PoseidonHash::new();
-    let phi_prev = if nu == 0 {
-        // φ_0 = 0 (dummy value)
-        ScalarField::from(0u64)
-    } else {
-        // Convert G1 point to scalar for hashing (simplified)
-        // TODO: Better conversion from G1 point to field element
-        let _phi_point = message
-            .latest_phi()
-            .ok_or_else(|| ProtocolError::CryptoError("No previous PRF output".to_string()))?;
-        ScalarField::from(1u64) // Placeholder
+    // φ_0 is the point at infinity (dummy value) until the first hop exists
+    let phi_prev = match message.latest_phi() {
+        Some(phi) => encode_g1(&phi.phi),
+        None => encode_g1(&G1Point::zero()),
     };
 
     let theta = hasher.hash_theta(&phi_prev, message.sid, message.pid, nu);
@@ -74,10 +74,15 @@ pub fn forward<R: Rng>(
 
     // Step 4: Select next hop
     // Extract ρ_{ν+1} from φ_{ν+1}
-    let rho_nu_plus_1 = extract_routing_value(&phi_nu_plus_1);
+    let rho_nu_plus_1 = extract_routing_value_with(&hasher, &phi_nu_plus_1);
 
-    // Use ρ and weight matrix to select next hop
-    let (k_r, pk_nu_plus_1) = select_next_hop(rho_nu_plus_1, weight_matrix, all_public_keys)?;
+    // Use ρ and the forwarder's weights to select next hop
+    let sender_index = all_public_keys
+        .iter()
+        .position(|key| key.pk == pk.pk)
+        .ok_or_else(|| ProtocolError::CryptoError("forwarder key is not registered".to_string()))?;
+    let (k_r, pk_nu_plus_1) =
+        select_next_hop(rho_nu_plus_1, weight_matrix, sender_index, all_public_keys)?;
 
     // Step 5: Create diversified public key ppk_{ν+1}
     let d = Diversifier {
@@ -88,15 +93,21 @@ pub fn forward<R: Rng>(
     // Step 6: Generate proof π_{ν+1}
     // TODO: Full proof generation using all five circuits
     let pi_nu_plus_1 = generate_forward_proof(
+        &hasher,
         pk,
         sk,
         message,
         &theta,
         &phi_nu_plus_1,
+        &pk_nu_plus_1,
         &ppk_nu_plus_1,
+        rho_nu_plus_1,
+        sender_index,
         k_r,
         &d,
         weight_matrix,
+        all_public_keys,
+        rng,
     )?;
 
     // Step 7: Create updated message m'
@@ -116,41 +127,111 @@ pub fn forward<R: Rng>(
 /// - π_1: Sender membership
 /// - π_2: Weight subtree
 /// - π_3: Receiver membership
-/// - π_{4,G1}: Schnorr bridging
+/// - π_{4,G1}: Bridging of the G1 commitments to the G2 keys
 /// - π_{4,G2}: Public key operations
-fn generate_forward_proof(
-    _pk: &PublicKey,
-    _sk: &SecretKey,
-    _message: &Message,
-    _theta: &ScalarField,
-    _phi_nu_plus_1: &PrfOutput,
-    _ppk_nu_plus_1: &DiversifiedPublicKey,
-    _k_r: usize,
-    _d: &Diversifier,
-    _weight_matrix: &WeightMatrix,
+#[allow(clippy::too_many_arguments)]
+fn generate_forward_proof<R: Rng>(
+    hasher: &PoseidonHash,
+    pk: &PublicKey,
+    sk: &SecretKey,
+    message: &Message,
+    theta: &ScalarField,
+    phi_nu_plus_1: &PrfOutput,
+    pk_nu_plus_1: &PublicKey,
+    ppk_nu_plus_1: &DiversifiedPublicKey,
+    rho: u32,
+    sender_index: usize,
+    k_r: usize,
+    d: &Diversifier,
+    weight_matrix: &WeightMatrix,
+    all_public_keys: &[PublicKey],
+    rng: &mut R,
 ) -> ProtocolResult<Proof> {
-    // TODO: Full proof generation
-    // For now, return a stub proof
-
-    // Create circuit
+    // TODO: Groth16 components
     let _circuit = ForwardCircuit::new();
 
-    // Generate witness
-    // ... (witness generation logic)
+    // π_{4,G2}: key ownership, diversification and PRF correctness
+    let statement = PublicKeyOpsStatement {
+        pid: message.pid,
+        sid: message.sid,
+        nu: message.hop_count(),
+        theta: *theta,
+        sender_ppk: message.current_ppk(),
+        receiver_ppk: ppk_nu_plus_1,
+        phi: phi_nu_plus_1,
+    };
+    let witness = PublicKeyOpsWitness::new_random(sk, pk_nu_plus_1, d.d, rng);
+    let pi_4_g2 = prove_public_key_ops(hasher, protocol_generators(), &statement, &witness, rng)?;
 
-    // Generate proofs for each component
-    // π_1, π_2, π_3, π_{4,G1}, π_{4,G2}
+    // π_{4,G1}: the same sk, d and r_r open the key commitment K, next to
+    // C_1 and C_3 committing to the key tree leaf (pk, md_{2,k_s}) and the
+    // neighbour tree leaf (pk_r, v_2), and ρ falls in the bucket (v_1, v_2]
+    let neighbours = sender_neighbours(weight_matrix, sender_index, all_public_keys)?;
+    let md_2 = MerkleTree::from_neighbours(hasher, &neighbours)?.root();
+    let position = weight_matrix
+        .get_weights(sender_index)
+        .iter()
+        .position(|&(j, _)| j == k_r)
+        .ok_or(ProtocolError::InvalidWeightSelection)?;
+    let bounds = cumulative_weights(neighbours.iter().map(|(_, weight)| *weight));
+    let v_1 = position.checked_sub(1).map_or(0, |previous| bounds[previous]);
+    let bridging_statement = BridgingStatement {
+        pid: message.pid,
+        sid: message.sid,
+        nu: message.hop_count(),
+        sender_ppk: message.current_ppk(),
+        receiver_ppk: ppk_nu_plus_1,
+        phi: phi_nu_plus_1,
+        rho,
+        pi_4_g2: &pi_4_g2,
+    };
+    let bridging_witness =
+        BridgingWitness::new_random(&witness, pk, md_2, (v_1, bounds[position]), rng);
+    let pi_4_g1 = prove_bridging(
+        hasher,
+        protocol_generators(),
+        &bridging_statement,
+        &bridging_witness,
+        rng,
+    )?;
+
+    let mut pi_4_g1_bytes = Vec::new();
+    pi_4_g1
+        .serialize_compressed(&mut pi_4_g1_bytes)
+        .map_err(|e| ProtocolError::SerializationError(e.to_string()))?;
+    let mut pi_4_g2_bytes = Vec::new();
+    pi_4_g2
+        .serialize_compressed(&mut pi_4_g2_bytes)
+        .map_err(|e| ProtocolError::SerializationError(e.to_string()))?;
 
-    // For now, stub
     Ok(Proof {
         pi_1: vec![0u8; 32],
         pi_2: vec![0u8; 32],
         pi_3: vec![0u8; 32],
-        pi_4_g1: vec![0u8; 32],
-        pi_4_g2: vec![0u8; 32],
+        pi_4_g1: pi_4_g1_bytes,
+        pi_4_g2: pi_4_g2_bytes,
     })
 }
 
+/// Routing list of node `index`, whose neighbour tree root is its metadata
+/// md_{2,k_s}
+fn sender_neighbours(
+    weight_matrix: &WeightMatrix,
+    index: usize,
+    all_public_keys: &[PublicKey],
+) -> ProtocolResult<Vec<(PublicKey, u32)>> {
+    weight_matrix
+        .get_weights(index)
+        .iter()
+        .map(|&(j, weight)| {
+            all_public_keys
+                .get(j)
+                .map(|pk| (pk.clone(), weight))
+                .ok_or(ProtocolError::InvalidWeightSelection)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
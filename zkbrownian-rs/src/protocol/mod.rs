@@ -10,6 +10,6 @@ pub mod bulletin_board;
 
 pub use forward::forward;
 pub use spawn::spawn;
-pub use verify::verify;
+pub use verify::{verify, verify_hops};
 pub use routing::*;
 pub use bulletin_board::*;
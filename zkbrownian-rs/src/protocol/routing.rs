@@ -68,7 +68,7 @@ impl WeightMatrix {
 /// Select next hop based on routing value ρ and weight matrix
 ///
 /// Algorithm:
-/// 1. Get weights for current node from weight matrix
+/// 1. Get weights for the forwarder from weight matrix
 /// 2. Treat weights as cumulative distribution
 /// 3. ρ (32-bit value from PRF) maps to range [0, 2^32)
 /// 4. Find which weight bucket ρ falls into
@@ -81,6 +81,7 @@ impl WeightMatrix {
 /// # Arguments
 /// * `rho` - 32-bit routing value from PRF
 /// * `weight_matrix` - Weight matrix
+/// * `sender_index` - Index of the forwarder, whose weights are used
 /// * `all_public_keys` - List of all node public keys
 ///
 /// # Returns
@@ -88,18 +89,15 @@ impl WeightMatrix {
 pub fn select_next_hop(
     rho: u32,
     weight_matrix: &WeightMatrix,
+    sender_index: usize,
     all_public_keys: &[PublicKey],
 ) -> ProtocolResult<(usize, PublicKey)> {
-    // For now, simple implementation:
-    // Get weights for "current node" (assume node 0 for simplicity)
-    // TODO: Track actual current node in message
-
     if all_public_keys.is_empty() {
         return Err(ProtocolError::InvalidWeightSelection);
     }
 
-    // Get weights for first node (placeholder)
-    let weights = weight_matrix.get_weights(0);
+    // The buckets are those of the forwarder's neighbour tree
+    let weights = weight_matrix.get_weights(sender_index);
 
     if weights.is_empty() {
         return Err(ProtocolError::InvalidWeightSelection);
@@ -165,15 +163,21 @@ mod tests {
         let matrix = WeightMatrix::uniform(3, WEIGHT_SUM);
 
         // Test selection with different ρ values
-        let (idx1, _) = select_next_hop(0, &matrix, &all_pks).unwrap();
-        let (idx2, _) = select_next_hop(u32::MAX / 2, &matrix, &all_pks).unwrap();
-        let (idx3, _) = select_next_hop(u32::MAX, &matrix, &all_pks).unwrap();
+        let (idx1, _) = select_next_hop(0, &matrix, 0, &all_pks).unwrap();
+        let (idx2, _) = select_next_hop(u32::MAX / 2, &matrix, 0, &all_pks).unwrap();
+        let (idx3, _) = select_next_hop(u32::MAX, &matrix, 0, &all_pks).unwrap();
 
         // All selections should be valid
         assert!(idx1 < 3);
         assert!(idx2 < 3);
         assert!(idx3 < 3);
 
+        // The forwarder's own weights are used, which never route to itself
+        for sender in 0..3 {
+            let (idx, _) = select_next_hop(u32::MAX / 2, &matrix, sender, &all_pks).unwrap();
+            assert_ne!(idx, sender);
+        }
+
         println!("Selected nodes: {}, {}, {}", idx1, idx2, idx3);
     }
 
@@ -2,11 +2,24 @@
 //!
 //! Creates initial messages: Spawn(sk, pid, sid) -> m
 
-use crate::crypto::curve_ops::diversify_with_diversifier;
-use crate::crypto::PoseidonHash;
+use crate::crypto::curve_ops::hash_to_g2;
 use crate::types::*;
+use ark_ec::CurveGroup;
 use rand::Rng;
 
+/// Domain separation tag for hashing (pid, sid) to the spawn base point
+pub const SPAWN_DST: &[u8] = b"ZKBROWNIAN-V01-CS01-with-BLS12381G2_XMD:SHA-256_SSWU_RO_SPAWN_";
+
+/// Hash(pid, sid) ∈ G2, the base point of ppk_0
+///
+/// The message is pid (4 bytes) followed by sid (8 bytes), little-endian.
+pub fn spawn_base(pid: PacketId, sid: SessionId) -> ProtocolResult<G2Point> {
+    let mut msg = Vec::with_capacity(12);
+    msg.extend_from_slice(&pid.to_le_bytes());
+    msg.extend_from_slice(&sid.to_le_bytes());
+    hash_to_g2(&msg, SPAWN_DST)
+}
+
 /// Spawn function: Spawn(pk, sk, pid, sid) -> m
 ///
 /// Creates a new message to send into the network.
@@ -34,22 +47,14 @@ pub fn spawn<R: Rng>(
     _rng: &mut R,
 ) -> ProtocolResult<Message> {
     // Step 1: Generate ppk_0
-    // ppk_0 ← Hash(pid, sid)^sk
-    //
-    // Interpretation: Hash(pid, sid) gives a point, then we exponentiate by sk
-    // For ElGamal-style diversified key, we treat Hash as a deterministic diversifier
-
-    let hasher = PoseidonHash::new();
-
-    // Hash pid and sid to get a "deterministic diversifier"
-    let diversifier_scalar = hasher.hash(&[ScalarField::from(pid as u64), ScalarField::from(sid)]);
-
-    let diversifier = Diversifier {
-        d: diversifier_scalar,
+    // ppk_0 ← (Hash(pid, sid)^sk, Hash(pid, sid)) with Hash a hash to G2, so
+    // ppk_0 has the shape of a diversified key whose diversifier nobody knows
+    let base = spawn_base(pid, sid)?;
+    let ppk_0 = DiversifiedPublicKey {
+        ppk_1: (base * sk.sk).into_affine(),
+        ppk_2: base,
     };
 
-    let (ppk_0, _) = diversify_with_diversifier(pk, &diversifier);
-
     // Step 2: Generate π_0
     // π_0 proves:
     // 1. ppk_0 is correctly derived from Hash(pid, sid) and sk
@@ -95,7 +100,7 @@ fn generate_spawn_proof(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::curve_ops::{check_diversified_ownership, keygen};
     use rand::thread_rng;
 
     #[test]
@@ -134,4 +139,16 @@ mod tests {
 
         assert_ne!(msg1.ppk_0.ppk_1, msg2.ppk_0.ppk_1);
     }
+
+    #[test]
+    fn test_spawn_ppk_from_hashed_base() {
+        let mut rng = thread_rng();
+        let (sk, pk) = keygen(&mut rng);
+
+        let message = spawn(&sk, &pk, 42, 1000, &mut rng).unwrap();
+        let base = spawn_base(42, 1000).unwrap();
+
+        assert_eq!(message.ppk_0.ppk_2, base);
+        assert!(check_diversified_ownership(&sk, &message.ppk_0));
+    }
 }
@@ -2,7 +2,19 @@
 //!
 //! Verifies message validity: Verify(m, h, C, P) -> {0, 1}
 
+use crate::crypto::{
+    batch_verify_prf_diversified, encode_g1, extract_routing_value_with, protocol_generators,
+    verify_prf_diversified, PoseidonHash,
+};
+use crate::proving::bridging::{bridging_verification_terms, BridgingProof, BridgingStatement};
+use crate::proving::pubkey_ops::{
+    public_key_ops_verification_terms, PublicKeyOpsProof, PublicKeyOpsStatement,
+};
+use crate::proving::sigma::VerificationTerms;
 use crate::types::*;
+use ark_ec::AffineRepr;
+use ark_serialize::CanonicalDeserialize;
+use rand::Rng;
 
 /// Verify function: Verify(m, h, C, P) -> bool
 ///
@@ -39,13 +51,48 @@ pub fn verify(
     }
 
     // Step 2: Verify each hop proof π_i
-    for (i, hop) in message.hops.iter().enumerate() {
-        if !verify_hop_proof(message, i, hop)? {
-            return Ok(false);
-        }
+    Ok(verify_hops(message, &mut rand::thread_rng()).is_ok())
+}
+
+/// Verify the hop proofs π_1, ..., π_h of a message in one batch
+///
+/// The per-hop work (θ, deserialization, Fiat–Shamir challenges) is done
+/// first. The PRF pairing equations of all hops are then checked in one
+/// randomized pairing product, and the equations of every π_{4,G1} and
+/// π_{4,G2} in one randomized MSM per group. If a batch fails, the hops are
+/// checked one by one.
+///
+/// Returns the index of the first invalid hop. Should a batch fail while
+/// every well-formed hop passes on its own, the index is that of the first
+/// malformed hop, or the number of hops if there is none.
+pub fn verify_hops<R: Rng>(message: &Message, rng: &mut R) -> Result<(), usize> {
+    let hasher = PoseidonHash::new();
+    let checks: Vec<HopChecks<'_>> = message
+        .hops
+        .iter()
+        .enumerate()
+        .map_while(|(i, hop)| hop_checks(&hasher, message, i, hop))
+        .collect();
+    // Hops after a malformed one do not need to be checked
+    let malformed = (checks.len() < message.hops.len()).then_some(checks.len());
+
+    let prf_instances: Vec<_> = checks
+        .iter()
+        .zip(&message.hops)
+        .map(|(check, hop)| (&hop.phi, check.theta, check.sender_ppk))
+        .collect();
+    let batch_valid = batch_verify_prf_diversified(&prf_instances, rng).is_ok()
+        && VerificationTerms::batch_check(checks.iter().flat_map(|check| &check.terms), rng);
+
+    if !batch_valid {
+        return Err(checks
+            .iter()
+            .zip(&message.hops)
+            .position(|(check, hop)| !check.check(hop))
+            .unwrap_or(checks.len()));
     }
 
-    Ok(true)
+    malformed.map_or(Ok(()), Err)
 }
 
 /// Verify the spawn proof π_0
@@ -55,32 +102,128 @@ fn verify_spawn_proof(_message: &Message) -> ProtocolResult<bool> {
     Ok(true)
 }
 
-/// Verify a single hop proof π_i
+/// Verification data of a single hop proof π_i
 ///
-/// Verifies:
+/// π_i shows:
 /// 1. Ownership of previous hop's ppk_{i-1}
 /// 2. Correct selection of next hop according to weight matrix
 /// 3. Correct derivation of ppk_i
 /// 4. Correct derivation of PRF output φ_i
-fn verify_hop_proof(_message: &Message, _hop_index: usize, _hop: &Hop) -> ProtocolResult<bool> {
-    // TODO: Implement actual verification of all five proof components
+///
+/// Item 4 is checked directly with the PRF pairing equation against the
+/// sender's diversified key: ppk_0 for the first hop, ppk_{i-1} afterwards.
+struct HopChecks<'m> {
+    theta: ScalarField,
+    sender_ppk: &'m DiversifiedPublicKey,
+    /// Equations of π_{4,G2} and π_{4,G1}
+    terms: [VerificationTerms; 2],
+}
+
+impl HopChecks<'_> {
+    /// Check this hop on its own
+    fn check(&self, hop: &Hop) -> bool {
+        verify_prf_diversified(&hop.phi, &self.theta, self.sender_ppk)
+            && self.terms.iter().all(VerificationTerms::check)
+    }
+}
+
+/// Derive θ_i and the verification equations of hop i
+///
+/// Returns `None` if a proof component is malformed.
+fn hop_checks<'m>(
+    hasher: &PoseidonHash,
+    message: &'m Message,
+    hop_index: usize,
+    hop: &'m Hop,
+) -> Option<HopChecks<'m>> {
+    let (sender_ppk, phi_prev) = match hop_index.checked_sub(1) {
+        Some(prev) => (&message.hops[prev].ppk, encode_g1(&message.hops[prev].phi.phi)),
+        None => (&message.ppk_0, encode_g1(&G1Point::zero())),
+    };
+    let theta = hasher.hash_theta(&phi_prev, message.sid, message.pid, hop_index);
+
+    // π_{4,G2}: key ownership, diversification of ppk_i and PRF correctness
+    let pi_4_g2 = PublicKeyOpsProof::deserialize_compressed(&hop.pi.pi_4_g2[..]).ok()?;
+    let statement = PublicKeyOpsStatement {
+        pid: message.pid,
+        sid: message.sid,
+        nu: hop_index,
+        theta,
+        sender_ppk,
+        receiver_ppk: &hop.ppk,
+        phi: &hop.phi,
+    };
+    let pi_4_g2_terms =
+        public_key_ops_verification_terms(hasher, protocol_generators(), &statement, &pi_4_g2)?;
+
+    // π_{4,G1}: the G1 commitments open to the values used in π_{4,G2}, and
+    // the committed bucket holds ρ_i
+    let pi_4_g1 = BridgingProof::deserialize_compressed(&hop.pi.pi_4_g1[..]).ok()?;
+    let bridging_statement = BridgingStatement {
+        pid: message.pid,
+        sid: message.sid,
+        nu: hop_index,
+        sender_ppk,
+        receiver_ppk: &hop.ppk,
+        phi: &hop.phi,
+        rho: extract_routing_value_with(hasher, &hop.phi),
+        pi_4_g2: &pi_4_g2,
+    };
+    let pi_4_g1_terms =
+        bridging_verification_terms(hasher, protocol_generators(), &bridging_statement, &pi_4_g1)?;
+
+    // TODO: Implement verification of the remaining proof components
     // - Verify π_1 (sender membership)
     // - Verify π_2 (weight subtree)
     // - Verify π_3 (receiver membership)
-    // - Verify π_{4,G1} (Schnorr bridging)
-    // - Verify π_{4,G2} (public key operations)
 
-    // For now, stub returns true
-    Ok(true)
+    Some(HopChecks {
+        theta,
+        sender_ppk,
+        terms: [pi_4_g2_terms, pi_4_g1_terms],
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::crypto::curve_ops::keygen;
+    use crate::crypto::prf::compute_prf;
+    use crate::protocol::forward::forward;
+    use crate::protocol::routing::WeightMatrix;
     use crate::protocol::spawn::spawn;
+    use crate::WEIGHT_SUM;
+    use ark_bls12_381::G1Projective;
+    use ark_ec::{CurveGroup, PrimeGroup};
     use rand::thread_rng;
 
+    fn empty_commitment() -> WeightCommitment {
+        WeightCommitment {
+            commitment: vec![],
+            metadata: vec![],
+        }
+    }
+
+    /// Spawn from node 0 and forward `hops` times along the selected route
+    fn forwarded_message(
+        nodes: &[(SecretKey, PublicKey)],
+        hops: usize,
+        rng: &mut impl rand::Rng,
+    ) -> Message {
+        let all_pks: Vec<PublicKey> = nodes.iter().map(|(_, pk)| pk.clone()).collect();
+        let weight_matrix = WeightMatrix::uniform(nodes.len(), WEIGHT_SUM);
+
+        let mut message = spawn(&nodes[0].0, &nodes[0].1, 1, 100, rng).unwrap();
+        let mut current = 0;
+        for _ in 0..hops {
+            let (sk, pk) = &nodes[current];
+            let (next, k_r, _d) = forward(pk, sk, &message, &weight_matrix, &all_pks, rng).unwrap();
+            message = next;
+            current = k_r;
+        }
+        message
+    }
+
     #[test]
     fn test_verify_spawn() {
         let mut rng = thread_rng();
@@ -115,4 +258,119 @@ mod tests {
         let result = verify(&message, 5, &weight_commitment, &all_pks).unwrap();
         assert!(!result);
     }
+
+    #[test]
+    fn test_verify_forwarded_chain() {
+        let mut rng = thread_rng();
+        let nodes: Vec<_> = (0..3).map(|_| keygen(&mut rng)).collect();
+        let all_pks: Vec<PublicKey> = nodes.iter().map(|(_, pk)| pk.clone()).collect();
+
+        let message = forwarded_message(&nodes, 3, &mut rng);
+        assert!(verify(&message, 3, &empty_commitment(), &all_pks).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_phi() {
+        let mut rng = thread_rng();
+        let nodes: Vec<_> = (0..3).map(|_| keygen(&mut rng)).collect();
+        let all_pks: Vec<PublicKey> = nodes.iter().map(|(_, pk)| pk.clone()).collect();
+
+        let mut message = forwarded_message(&nodes, 2, &mut rng);
+        message.hops[1].phi.phi = G1Projective::generator().into_affine();
+        assert!(!verify(&message, 2, &empty_commitment(), &all_pks).unwrap());
+
+        // Swapping φ values between hops breaks the θ chain as well
+        let mut message = forwarded_message(&nodes, 2, &mut rng);
+        let phi_0 = message.hops[0].phi.clone();
+        message.hops[0].phi = message.hops[1].phi.clone();
+        message.hops[1].phi = phi_0;
+        assert!(!verify(&message, 2, &empty_commitment(), &all_pks).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_forwarder() {
+        let mut rng = thread_rng();
+        let (sk1, pk1) = keygen(&mut rng);
+        let (sk2, pk2) = keygen(&mut rng);
+        let all_pks = vec![pk1.clone(), pk2.clone()];
+        let weight_matrix = WeightMatrix::uniform(2, WEIGHT_SUM);
+
+        // Node 2 cannot prove ownership of a message spawned by node 1
+        let message = spawn(&sk1, &pk1, 1, 100, &mut rng).unwrap();
+        assert!(forward(&pk2, &sk2, &message, &weight_matrix, &all_pks, &mut rng).is_err());
+
+        // A φ computed with node 2's key is rejected next to a valid proof
+        let (mut forwarded, _k_r, _d) =
+            forward(&pk1, &sk1, &message, &weight_matrix, &all_pks, &mut rng).unwrap();
+        let theta = PoseidonHash::new().hash_theta(&encode_g1(&G1Point::zero()), 100, 1, 0);
+        forwarded.hops[0].phi = compute_prf(&theta, &sk2, &G1Point::generator()).unwrap();
+
+        assert!(!verify(&forwarded, 1, &empty_commitment(), &all_pks).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_swapped_pubkey_ops_proof() {
+        let mut rng = thread_rng();
+        let nodes: Vec<_> = (0..3).map(|_| keygen(&mut rng)).collect();
+        let all_pks: Vec<PublicKey> = nodes.iter().map(|(_, pk)| pk.clone()).collect();
+
+        // π_{4,G2} of one hop does not verify for another
+        let mut message = forwarded_message(&nodes, 2, &mut rng);
+        message.hops[1].pi.pi_4_g2 = message.hops[0].pi.pi_4_g2.clone();
+        assert!(!verify(&message, 2, &empty_commitment(), &all_pks).unwrap());
+
+        let mut message = forwarded_message(&nodes, 1, &mut rng);
+        message.hops[0].pi.pi_4_g2 = vec![0u8; 32];
+        assert!(!verify(&message, 1, &empty_commitment(), &all_pks).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_swapped_bridging_proof() {
+        let mut rng = thread_rng();
+        let nodes: Vec<_> = (0..3).map(|_| keygen(&mut rng)).collect();
+        let all_pks: Vec<PublicKey> = nodes.iter().map(|(_, pk)| pk.clone()).collect();
+
+        // π_{4,G1} is bound to the π_{4,G2} of its own hop
+        let mut message = forwarded_message(&nodes, 2, &mut rng);
+        message.hops[1].pi.pi_4_g1 = message.hops[0].pi.pi_4_g1.clone();
+        assert!(!verify(&message, 2, &empty_commitment(), &all_pks).unwrap());
+
+        // Re-proving the hop gives a new π_{4,G2} that the old π_{4,G1} does
+        // not accept
+        let mut message = forwarded_message(&nodes, 1, &mut rng);
+        let mut other = message.clone();
+        other.hops.clear();
+        let weight_matrix = WeightMatrix::uniform(nodes.len(), WEIGHT_SUM);
+        let (other, _, _) =
+            forward(&nodes[0].1, &nodes[0].0, &other, &weight_matrix, &all_pks, &mut rng).unwrap();
+        message.hops[0].pi.pi_4_g2 = other.hops[0].pi.pi_4_g2.clone();
+        message.hops[0].ppk = other.hops[0].ppk.clone();
+        assert!(!verify(&message, 1, &empty_commitment(), &all_pks).unwrap());
+
+        message.hops[0].pi.pi_4_g1 = vec![0u8; 32];
+        assert!(!verify(&message, 1, &empty_commitment(), &all_pks).unwrap());
+    }
+
+    #[test]
+    fn test_verify_hops_reports_first_invalid_hop() {
+        let mut rng = thread_rng();
+        let nodes: Vec<_> = (0..3).map(|_| keygen(&mut rng)).collect();
+        let message = forwarded_message(&nodes, 4, &mut rng);
+        assert_eq!(verify_hops(&message, &mut rng), Ok(()));
+
+        // A bad sigma proof at hop 1 and a malformed one at hop 3
+        let mut tampered = message.clone();
+        tampered.hops[1].pi.pi_4_g2 = message.hops[2].pi.pi_4_g2.clone();
+        tampered.hops[3].pi.pi_4_g1 = vec![];
+        assert_eq!(verify_hops(&tampered, &mut rng), Err(1));
+
+        let mut tampered = message.clone();
+        tampered.hops[3].pi.pi_4_g1 = vec![];
+        assert_eq!(verify_hops(&tampered, &mut rng), Err(3));
+
+        // A wrong φ at hop 2 also breaks the θ of hop 3
+        let mut tampered = message;
+        tampered.hops[2].phi = tampered.hops[0].phi.clone();
+        assert_eq!(verify_hops(&tampered, &mut rng), Err(2));
+    }
 }
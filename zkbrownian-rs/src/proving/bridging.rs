@@ -0,0 +1,633 @@
+//! π_{4,G1}: bridging the G1 commitments to the G2 keys
+//!
+//! The membership proofs are over G1 and verify with respect to Pedersen
+//! commitments to the values their leaves hash ([`leaf_values`]):
+//!
+//! ```text
+//! C_1 = Σ_i enc(pk_s)_i · G_i + md  · G_8 + r_1 · H'     (key tree leaf)
+//! C_3 = Σ_i enc(pk_r)_i · G_i + v_2 · G_8 + r_3 · H'     (neighbour tree leaf)
+//! ```
+//!
+//! where enc = encode_g2 holds the coordinates of a key as seven limbs and
+//! flags, md = md_{2,k_s} is the sender's neighbour tree root and v_2 the
+//! upper end of the receiver's weight bucket. This is the
+//! G_1^{pk_x} G_2^{pk_y} G_3^{md} H^r of the spec with the coordinates in
+//! limbs; [`crate::proving::circuits::SenderMembershipCircuit`] and
+//! [`crate::proving::circuits::ReceiverMembershipCircuit`] link their D to C_1
+//! and C_3 through [`crate::proving::commitment_link`].
+//!
+//! π_{4,G2} works with pk* = sk · G + r · H, ppk_{r,2} = d · G and
+//! A = r_r · H_1 in G2. This sigma proof opens C_1, C_3 and the key
+//! commitment
+//!
+//! ```text
+//! K = sk · G_1 + d · G_2 + r_r · G_3 + r_k · G_4
+//! ```
+//!
+//! and shows the G2 relations with one response per witness, so the sk, d
+//! and r_r in K are the ones used in G2. The transcript absorbs the hop
+//! context and all of π_{4,G2}, so the two proofs cannot be mixed across
+//! hops.
+//!
+//! It also carries the range proof of π_4 ([`crate::proving::range`]):
+//! commitments C_{v_1} = v_1 · G_8 + s_1 · H' and C_{v_2} = v_2 · G_8 + s_2 · H',
+//! with v_2 shared with C_3, and a [`BucketProof`] of v_1 < ρ + 1 ≤ v_2 for
+//! the routing value ρ = ρ_{ν+1}. [`crate::protocol::routing::select_next_hop`]
+//! routes ρ ∈ [0, 2^32) to the neighbour with v_1 ≤ ρ < v_2, which is the
+//! bucket v_1 < ρ ≤ v_2 of the spec for ρ counted from 1.
+//!
+//! TODO: show that the coordinates opened by C_1 are those of sk · G and
+//! the ones opened by C_3 those of the pk_r inside C_r. This is the
+//! pk* = pk · H^r "in coordinate form" of the spec; it is not linear in the
+//! committed values, as adding points is not linear in their coordinates,
+//! so it needs G2 arithmetic in a circuit rather than a sigma proof. Until
+//! then C_1 and C_3 may open to keys other than the ones used in G2.
+//! Likewise v_1 is only tied to the neighbour tree by π_2.
+
+use crate::crypto::generators::Generators;
+use crate::crypto::merkle::{leaf_values, LEAF_VALUES};
+use crate::crypto::pedersen::{G1PedersenParams, PedersenOpening};
+use crate::crypto::poseidon::PoseidonHash;
+use crate::proving::pubkey_ops::{PublicKeyOpsProof, PublicKeyOpsWitness};
+use crate::proving::range::{prove_bucket, verify_bucket, BucketProof};
+use crate::proving::sigma::{LinearRelation, SigmaProof, VerificationTerms};
+use crate::proving::transcript::{PoseidonTranscript, Transcript};
+use crate::types::{
+    DiversifiedPublicKey, G1Point, PacketId, PrfOutput, ProtocolResult, PublicKey, ScalarField,
+    SessionId,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use rand::Rng;
+
+/// Protocol label of the π_{4,G1} transcript
+pub const BRIDGING_LABEL: &[u8] = b"zkbrownian/pi_4_g1";
+
+/// Witness indices in the relation
+const SK: usize = 0;
+const PK_RANDOMNESS: usize = 1;
+const DIVERSIFIER: usize = 2;
+const RECEIVER_RANDOMNESS: usize = 3;
+const KEY_COMMITMENT_RANDOMNESS: usize = 4;
+/// First of the [`LEAF_VALUES`] values opened by C_1
+const SENDER_LEAF: usize = 5;
+const SENDER_RANDOMNESS: usize = SENDER_LEAF + LEAF_VALUES;
+/// First of the [`LEAF_VALUES`] values opened by C_3
+const RECEIVER_LEAF: usize = SENDER_RANDOMNESS + 1;
+/// v_2, the last value opened by C_3
+const UPPER_BOUND: usize = RECEIVER_LEAF + LEAF_VALUES - 1;
+const RECEIVER_COMMITMENT_RANDOMNESS: usize = RECEIVER_LEAF + LEAF_VALUES;
+const UPPER_BOUND_RANDOMNESS: usize = RECEIVER_COMMITMENT_RANDOMNESS + 1;
+const LOWER_BOUND: usize = UPPER_BOUND_RANDOMNESS + 1;
+const LOWER_BOUND_RANDOMNESS: usize = LOWER_BOUND + 1;
+const NUM_WITNESSES: usize = LOWER_BOUND_RANDOMNESS + 1;
+
+/// Pedersen parameters of C_1 and C_3: bases G_1..G_8, blinding base H'
+pub fn leaf_commitment_params(generators: &Generators) -> ProtocolResult<G1PedersenParams> {
+    G1PedersenParams::from_generators(generators, LEAF_VALUES)
+}
+
+/// Pedersen parameters of K: bases G_1..G_3, blinding base G_4
+fn key_commitment_params(generators: &Generators) -> ProtocolResult<G1PedersenParams> {
+    G1PedersenParams::from_generators(generators, 3)
+}
+
+/// Pedersen parameters of C_{v_1} and C_{v_2}: base G_8, blinding base H'
+///
+/// G_8 is the base of the last leaf value, so v_2 has the same base in C_3.
+pub fn bound_commitment_params(generators: &Generators) -> ProtocolResult<G1PedersenParams> {
+    let leaf = leaf_commitment_params(generators)?;
+    Ok(G1PedersenParams::new(
+        vec![leaf.bases[LEAF_VALUES - 1]],
+        leaf.blinding,
+    ))
+}
+
+/// Public inputs of π_{4,G1}
+#[derive(Clone, Debug)]
+pub struct BridgingStatement<'a> {
+    pub pid: PacketId,
+    pub sid: SessionId,
+    /// Hop index ν of the sender
+    pub nu: usize,
+    /// ppk_ν, owned by the forwarder
+    pub sender_ppk: &'a DiversifiedPublicKey,
+    /// ppk_{ν+1}, addressed to the receiver
+    pub receiver_ppk: &'a DiversifiedPublicKey,
+    /// φ_{ν+1}
+    pub phi: &'a PrfOutput,
+    /// Routing value ρ_{ν+1}, recomputed by the verifier from φ_{ν+1}
+    pub rho: u32,
+    /// π_{4,G2} of the same hop
+    pub pi_4_g2: &'a PublicKeyOpsProof,
+}
+
+/// Secret inputs of π_{4,G1}
+#[derive(Clone, Debug)]
+pub struct BridgingWitness<'a> {
+    /// Witness of π_{4,G2}: sk, r, pk_r, r_r and d
+    pub pubkey_ops: &'a PublicKeyOpsWitness<'a>,
+    /// Sender public key pk_s, the key of the key tree leaf
+    pub sender_pk: &'a PublicKey,
+    /// Sender metadata md_{2,k_s} committed next to pk_s
+    pub metadata: ScalarField,
+    /// Lower end v_1 of the receiver's weight bucket, 0 for the first
+    /// neighbour
+    pub lower_bound: u64,
+    /// Upper end v_2 of the receiver's weight bucket, committed next to pk_r
+    pub upper_bound: u64,
+    /// Blinding r_k of K
+    pub key_commitment_randomness: ScalarField,
+    /// Blinding r_1 of C_1
+    pub sender_randomness: ScalarField,
+    /// Blinding r_3 of C_3
+    pub receiver_commitment_randomness: ScalarField,
+    /// Blinding s_1 of C_{v_1}
+    pub lower_bound_randomness: ScalarField,
+    /// Blinding s_2 of C_{v_2}
+    pub upper_bound_randomness: ScalarField,
+}
+
+/// The G1 commitments of π_{4,G1}
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BridgingCommitments {
+    /// K = sk · G_1 + d · G_2 + r_r · G_3 + r_k · G_4
+    pub key: G1Point,
+    /// C_1, committing to the key tree leaf (pk_s, md)
+    pub sender: G1Point,
+    /// C_3, committing to the neighbour tree leaf (pk_r, v_2)
+    pub receiver: G1Point,
+    /// C_{v_1} = v_1 · G_8 + s_1 · H'
+    pub lower_bound: G1Point,
+    /// C_{v_2} = v_2 · G_8 + s_2 · H'
+    pub upper_bound: G1Point,
+}
+
+/// π_{4,G1}, stored in the `pi_4_g1` slot of [`crate::types::Proof`]
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BridgingProof {
+    pub commitments: BridgingCommitments,
+    /// Sigma proof for the linear relation
+    pub sigma: SigmaProof,
+    /// v_1 < ρ + 1 ≤ v_2 for C_{v_1} and C_{v_2}
+    pub bucket: BucketProof,
+}
+
+impl<'a> BridgingWitness<'a> {
+    /// Witness for the bucket (v_1, v_2] with fresh blinding factors
+    pub fn new_random<R: Rng>(
+        pubkey_ops: &'a PublicKeyOpsWitness<'a>,
+        sender_pk: &'a PublicKey,
+        metadata: ScalarField,
+        (lower_bound, upper_bound): (u64, u64),
+        rng: &mut R,
+    ) -> Self {
+        Self {
+            pubkey_ops,
+            sender_pk,
+            metadata,
+            lower_bound,
+            upper_bound,
+            key_commitment_randomness: ScalarField::rand(rng),
+            sender_randomness: ScalarField::rand(rng),
+            receiver_commitment_randomness: ScalarField::rand(rng),
+            lower_bound_randomness: ScalarField::rand(rng),
+            upper_bound_randomness: ScalarField::rand(rng),
+        }
+    }
+
+    /// Opening of C_1, for linking π_1 to it
+    pub fn sender_opening(&self) -> PedersenOpening {
+        PedersenOpening {
+            values: leaf_values(self.sender_pk, self.metadata),
+            randomness: self.sender_randomness,
+        }
+    }
+
+    /// Opening of C_3, for linking π_3 to it
+    pub fn receiver_opening(&self) -> PedersenOpening {
+        PedersenOpening {
+            values: leaf_values(
+                self.pubkey_ops.receiver_pk,
+                ScalarField::from(self.upper_bound),
+            ),
+            randomness: self.receiver_commitment_randomness,
+        }
+    }
+
+    /// The commitments K, C_1, C_3, C_{v_1} and C_{v_2}
+    fn commitments(&self, generators: &Generators) -> ProtocolResult<BridgingCommitments> {
+        let ops = self.pubkey_ops;
+        let leaf = leaf_commitment_params(generators)?;
+        let bounds = bound_commitment_params(generators)?;
+        let bound = |value: u64, randomness: &ScalarField| {
+            bounds
+                .commit(&[ScalarField::from(value)], randomness)
+                .map(|c| c.point)
+        };
+
+        Ok(BridgingCommitments {
+            key: key_commitment_params(generators)?
+                .commit(
+                    &[ops.sk.sk, ops.d, ops.receiver_randomness],
+                    &self.key_commitment_randomness,
+                )?
+                .point,
+            sender: leaf.open(&self.sender_opening())?.point,
+            receiver: leaf.open(&self.receiver_opening())?.point,
+            lower_bound: bound(self.lower_bound, &self.lower_bound_randomness)?,
+            upper_bound: bound(self.upper_bound, &self.upper_bound_randomness)?,
+        })
+    }
+}
+
+impl BridgingStatement<'_> {
+    /// The linear relation for the given commitments
+    pub fn relation(
+        &self,
+        generators: &Generators,
+        commitments: &BridgingCommitments,
+    ) -> ProtocolResult<LinearRelation> {
+        let key = key_commitment_params(generators)?;
+        let leaf = leaf_commitment_params(generators)?;
+        let leaf_terms = |first: usize, randomness: usize| {
+            let mut terms: Vec<_> = leaf
+                .bases
+                .iter()
+                .enumerate()
+                .map(|(i, base)| (first + i, *base))
+                .collect();
+            terms.push((randomness, leaf.blinding));
+            terms
+        };
+        let (g_8, h) = (leaf.bases[LEAF_VALUES - 1], leaf.blinding);
+        let g = generators.g2_base;
+        let h2 = generators.g2_generators[0];
+        let h2_1 = generators.g2_generators[1];
+
+        let mut relation = LinearRelation::new(NUM_WITNESSES);
+        relation
+            // K = sk · G_1 + d · G_2 + r_r · G_3 + r_k · G_4
+            .add_g1(
+                commitments.key,
+                vec![
+                    (SK, key.bases[0]),
+                    (DIVERSIFIER, key.bases[1]),
+                    (RECEIVER_RANDOMNESS, key.bases[2]),
+                    (KEY_COMMITMENT_RANDOMNESS, key.blinding),
+                ],
+            )
+            // C_1 = Σ_i enc(pk_s)_i · G_i + md · G_8 + r_1 · H'
+            .add_g1(
+                commitments.sender,
+                leaf_terms(SENDER_LEAF, SENDER_RANDOMNESS),
+            )
+            // C_3 = Σ_i enc(pk_r)_i · G_i + v_2 · G_8 + r_3 · H'
+            .add_g1(
+                commitments.receiver,
+                leaf_terms(RECEIVER_LEAF, RECEIVER_COMMITMENT_RANDOMNESS),
+            )
+            // C_{v_2} = v_2 · G_8 + s_2 · H'
+            .add_g1(
+                commitments.upper_bound,
+                vec![(UPPER_BOUND, g_8), (UPPER_BOUND_RANDOMNESS, h)],
+            )
+            // C_{v_1} = v_1 · G_8 + s_1 · H'
+            .add_g1(
+                commitments.lower_bound,
+                vec![(LOWER_BOUND, g_8), (LOWER_BOUND_RANDOMNESS, h)],
+            )
+            // pk* = sk · G + r · H
+            .add_g2(self.pi_4_g2.pk_star, vec![(SK, g), (PK_RANDOMNESS, h2)])
+            // ppk_{r,2} = d · G
+            .add_g2(self.receiver_ppk.ppk_2, vec![(DIVERSIFIER, g)])
+            // A = r_r · H_1
+            .add_g2(self.pi_4_g2.receiver_aux, vec![(RECEIVER_RANDOMNESS, h2_1)]);
+        Ok(relation)
+    }
+
+    /// Transcript bound to the hop context, π_{4,G2} and the commitments
+    pub fn transcript<'h>(
+        &self,
+        hasher: &'h PoseidonHash,
+        commitments: &BridgingCommitments,
+    ) -> PoseidonTranscript<'h> {
+        let mut transcript = PoseidonTranscript::new(hasher, BRIDGING_LABEL);
+        transcript.append_hop_context(
+            self.pid,
+            self.sid,
+            self.nu,
+            self.sender_ppk,
+            self.receiver_ppk,
+            self.phi,
+        );
+        self.pi_4_g2.append_to_transcript(&mut transcript);
+        transcript.append_g1(b"key_commitment", &commitments.key);
+        transcript.append_g1(b"sender_commitment", &commitments.sender);
+        transcript.append_g1(b"receiver_commitment", &commitments.receiver);
+        transcript.append_g1(b"lower_bound", &commitments.lower_bound);
+        transcript.append_g1(b"upper_bound", &commitments.upper_bound);
+        transcript
+    }
+
+    /// ρ + 1, the routing value counted from 1 that the bucket proof covers
+    fn bucket_value(&self) -> u64 {
+        self.rho as u64 + 1
+    }
+}
+
+/// Generate π_{4,G1}
+pub fn prove_bridging<R: Rng>(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &BridgingStatement<'_>,
+    witness: &BridgingWitness<'_>,
+    rng: &mut R,
+) -> ProtocolResult<BridgingProof> {
+    let ops = witness.pubkey_ops;
+    let commitments = witness.commitments(generators)?;
+
+    let mut witnesses = vec![ScalarField::from(0u64); NUM_WITNESSES];
+    witnesses[SK] = ops.sk.sk;
+    witnesses[PK_RANDOMNESS] = ops.pk_randomness;
+    witnesses[DIVERSIFIER] = ops.d;
+    witnesses[RECEIVER_RANDOMNESS] = ops.receiver_randomness;
+    witnesses[KEY_COMMITMENT_RANDOMNESS] = witness.key_commitment_randomness;
+    witnesses[SENDER_LEAF..SENDER_RANDOMNESS].copy_from_slice(&witness.sender_opening().values);
+    witnesses[SENDER_RANDOMNESS] = witness.sender_randomness;
+    witnesses[RECEIVER_LEAF..RECEIVER_COMMITMENT_RANDOMNESS]
+        .copy_from_slice(&witness.receiver_opening().values);
+    witnesses[RECEIVER_COMMITMENT_RANDOMNESS] = witness.receiver_commitment_randomness;
+    witnesses[UPPER_BOUND_RANDOMNESS] = witness.upper_bound_randomness;
+    witnesses[LOWER_BOUND] = ScalarField::from(witness.lower_bound);
+    witnesses[LOWER_BOUND_RANDOMNESS] = witness.lower_bound_randomness;
+
+    let relation = statement.relation(generators, &commitments)?;
+    let mut transcript = statement.transcript(hasher, &commitments);
+    let sigma = relation.prove(&witnesses, &mut transcript, rng)?;
+    let bucket = prove_bucket(
+        &bound_commitment_params(generators)?,
+        statement.bucket_value(),
+        &commitments.lower_bound,
+        (witness.lower_bound, &witness.lower_bound_randomness),
+        &commitments.upper_bound,
+        (witness.upper_bound, &witness.upper_bound_randomness),
+        &mut transcript,
+        rng,
+    )?;
+
+    Ok(BridgingProof {
+        commitments,
+        sigma,
+        bucket,
+    })
+}
+
+/// Recompute the challenge of π_{4,G1} and flatten its verification equations
+///
+/// The bucket proof is checked here rather than batched; `None` means it
+/// failed or the proof is malformed.
+pub fn bridging_verification_terms(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &BridgingStatement<'_>,
+    proof: &BridgingProof,
+) -> Option<VerificationTerms> {
+    let relation = statement.relation(generators, &proof.commitments).ok()?;
+    let mut transcript = statement.transcript(hasher, &proof.commitments);
+    let terms = relation.verification_terms(&proof.sigma, &mut transcript)?;
+    verify_bucket(
+        &bound_commitment_params(generators).ok()?,
+        statement.bucket_value(),
+        &proof.commitments.lower_bound,
+        &proof.commitments.upper_bound,
+        &proof.bucket,
+        &mut transcript,
+    )
+    .then_some(terms)
+}
+
+/// Verify π_{4,G1}
+///
+/// π_{4,G2} is only bound here, not verified; check it with
+/// [`crate::proving::pubkey_ops::verify_public_key_ops`].
+pub fn verify_bridging(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &BridgingStatement<'_>,
+    proof: &BridgingProof,
+) -> bool {
+    bridging_verification_terms(hasher, generators, statement, proof)
+        .is_some_and(|terms| terms.check())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::generators::protocol_generators;
+    use crate::proving::pubkey_ops::prove_public_key_ops;
+    use crate::proving::pubkey_ops::test_utils::Fixture;
+    use ark_ec::AffineRepr;
+    use rand::thread_rng;
+
+    /// Receiver's bucket (v_1, v_2] in the tests
+    const BOUNDS: (u64, u64) = (1 << 30, 1 << 31);
+    /// A routing value the receiver's bucket covers
+    const RHO: u32 = (1 << 30) + 5;
+
+    fn bridging_statement<'a>(
+        f: &'a Fixture,
+        pi_4_g2: &'a PublicKeyOpsProof,
+    ) -> BridgingStatement<'a> {
+        BridgingStatement {
+            pid: 1,
+            sid: 100,
+            nu: 2,
+            sender_ppk: &f.sender_ppk,
+            receiver_ppk: &f.receiver_ppk,
+            phi: &f.phi,
+            rho: RHO,
+            pi_4_g2,
+        }
+    }
+
+    #[test]
+    fn test_bridging_roundtrip() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+
+        let ops_witness = PublicKeyOpsWitness::new_random(&f.sk, &f.receiver_pk, f.d, &mut rng);
+        let pi_4_g2 =
+            prove_public_key_ops(&hasher, generators, &f.statement(), &ops_witness, &mut rng)
+                .unwrap();
+
+        let statement = bridging_statement(&f, &pi_4_g2);
+        let md = ScalarField::from(7u64);
+        let witness = BridgingWitness::new_random(&ops_witness, &f.pk, md, BOUNDS, &mut rng);
+        let proof = prove_bridging(&hasher, generators, &statement, &witness, &mut rng).unwrap();
+        assert!(verify_bridging(&hasher, generators, &statement, &proof));
+
+        // C_1 and C_3 open to the leaf values the membership circuits hash
+        let leaf = leaf_commitment_params(generators).unwrap();
+        let c_1 = leaf
+            .commit(&leaf_values(&f.pk, md), &witness.sender_randomness)
+            .unwrap();
+        assert_eq!(c_1.point, proof.commitments.sender);
+        let c_3 = leaf
+            .commit(
+                &leaf_values(&f.receiver_pk, ScalarField::from(BOUNDS.1)),
+                &witness.receiver_commitment_randomness,
+            )
+            .unwrap();
+        assert_eq!(c_3.point, proof.commitments.receiver);
+
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let decoded = BridgingProof::deserialize_compressed(&bytes[..]).unwrap();
+        assert!(verify_bridging(&hasher, generators, &statement, &decoded));
+    }
+
+    #[test]
+    fn test_bridging_bound_to_pubkey_ops_proof() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+
+        let ops_witness = PublicKeyOpsWitness::new_random(&f.sk, &f.receiver_pk, f.d, &mut rng);
+        let statement = f.statement();
+        let pi_4_g2 =
+            prove_public_key_ops(&hasher, generators, &statement, &ops_witness, &mut rng).unwrap();
+        let other_pi_4_g2 =
+            prove_public_key_ops(&hasher, generators, &statement, &ops_witness, &mut rng).unwrap();
+
+        let witness = BridgingWitness::new_random(
+            &ops_witness,
+            &f.pk,
+            ScalarField::from(0u64),
+            BOUNDS,
+            &mut rng,
+        );
+        let proof = prove_bridging(
+            &hasher,
+            generators,
+            &bridging_statement(&f, &pi_4_g2),
+            &witness,
+            &mut rng,
+        )
+        .unwrap();
+
+        // Another valid π_{4,G2} for the same hop and witness still changes
+        // the transcript
+        assert_eq!(other_pi_4_g2.pk_star, pi_4_g2.pk_star);
+        assert!(!verify_bridging(
+            &hasher,
+            generators,
+            &bridging_statement(&f, &other_pi_4_g2),
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_bridging_rejects_different_keys() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+        let (other_sk, _) = keygen(&mut rng);
+        let md = ScalarField::from(0u64);
+
+        let ops_witness = PublicKeyOpsWitness::new_random(&f.sk, &f.receiver_pk, f.d, &mut rng);
+        let pi_4_g2 =
+            prove_public_key_ops(&hasher, generators, &f.statement(), &ops_witness, &mut rng)
+                .unwrap();
+        let statement = bridging_statement(&f, &pi_4_g2);
+
+        // The honest prover refuses to commit to another key in K
+        let mut other_ops = ops_witness.clone();
+        other_ops.sk = &other_sk;
+        let witness = BridgingWitness::new_random(&other_ops, &f.pk, md, BOUNDS, &mut rng);
+        assert!(prove_bridging(&hasher, generators, &statement, &witness, &mut rng).is_err());
+
+        // K committing to another key does not match the shared response
+        let honest = BridgingWitness::new_random(&ops_witness, &f.pk, md, BOUNDS, &mut rng);
+        let mut proof = prove_bridging(&hasher, generators, &statement, &honest, &mut rng).unwrap();
+        let key = key_commitment_params(generators).unwrap();
+        proof.commitments.key = key
+            .commit(
+                &[other_sk.sk, f.d, ops_witness.receiver_randomness],
+                &honest.key_commitment_randomness,
+            )
+            .unwrap()
+            .point;
+        assert!(!verify_bridging(&hasher, generators, &statement, &proof));
+
+        // Likewise for a key commitment with another diversifier
+        let mut proof = prove_bridging(&hasher, generators, &statement, &honest, &mut rng).unwrap();
+        proof.commitments.key = (proof.commitments.key.into_group() + key.bases[1]).into();
+        assert!(!verify_bridging(&hasher, generators, &statement, &proof));
+
+        // C_3 swapped for a commitment to another receiver leaf
+        let mut proof = prove_bridging(&hasher, generators, &statement, &honest, &mut rng).unwrap();
+        let (_, outsider) = keygen(&mut rng);
+        proof.commitments.receiver = leaf_commitment_params(generators)
+            .unwrap()
+            .commit(
+                &leaf_values(&outsider, ScalarField::from(BOUNDS.1)),
+                &honest.receiver_commitment_randomness,
+            )
+            .unwrap()
+            .point;
+        assert!(!verify_bridging(&hasher, generators, &statement, &proof));
+    }
+
+    #[test]
+    fn test_bridging_bucket() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+        let md = ScalarField::from(0u64);
+
+        let ops_witness = PublicKeyOpsWitness::new_random(&f.sk, &f.receiver_pk, f.d, &mut rng);
+        let pi_4_g2 =
+            prove_public_key_ops(&hasher, generators, &f.statement(), &ops_witness, &mut rng)
+                .unwrap();
+        let witness = BridgingWitness::new_random(&ops_witness, &f.pk, md, BOUNDS, &mut rng);
+
+        // select_next_hop routes v_1 ≤ ρ < v_2 here, i.e. v_1 < ρ + 1 ≤ v_2
+        let mut statement = bridging_statement(&f, &pi_4_g2);
+        for rho in [BOUNDS.0 as u32, (BOUNDS.1 - 1) as u32] {
+            statement.rho = rho;
+            let proof =
+                prove_bridging(&hasher, generators, &statement, &witness, &mut rng).unwrap();
+            assert!(verify_bridging(&hasher, generators, &statement, &proof));
+        }
+        for rho in [BOUNDS.0 as u32 - 1, BOUNDS.1 as u32] {
+            statement.rho = rho;
+            assert!(prove_bridging(&hasher, generators, &statement, &witness, &mut rng).is_err());
+        }
+
+        // A proof for one ρ does not cover another one in the same bucket
+        statement.rho = RHO;
+        let proof = prove_bridging(&hasher, generators, &statement, &witness, &mut rng).unwrap();
+        statement.rho = RHO + 1;
+        assert!(!verify_bridging(&hasher, generators, &statement, &proof));
+
+        // C_{v_2} must open to the v_2 of C_3
+        statement.rho = RHO;
+        let mut proof = proof;
+        proof.commitments.upper_bound = bound_commitment_params(generators)
+            .unwrap()
+            .commit(
+                &[ScalarField::from(BOUNDS.1 + 1)],
+                &witness.upper_bound_randomness,
+            )
+            .unwrap()
+            .point;
+        assert!(!verify_bridging(&hasher, generators, &statement, &proof));
+    }
+}
@@ -4,96 +4,598 @@
 //! - π_1: Merkle tree membership for sender public key
 //! - π_2: Weight sub-tree proofs (Catalano-Fiore variant)
 //! - π_3: Merkle tree membership for receiver public key
-//! - π_{4,G1}: Lightweight Schnorr bridging proof in G1
-//! - π_{4,G2}: Public key operations proof in G2
+//! - π_{4,G1}: Bridging proof in G1, a sigma proof implemented in
+//!   [`crate::proving::bridging`]
+//! - π_{4,G2}: Public key operations proof in G2, a sigma proof implemented
+//!   in [`crate::proving::pubkey_ops`]
+//!
+//! The Groth16 circuits are [`ConstraintSynthesizer`]s over the BLS12-381
+//! scalar field. A circuit with no assignment (`Default`) has the same shape
+//! as an assigned one and is used for the setup.
+//!
+//! The membership circuits allocate the values of their leaf first, so a
+//! commit-carrying proof ([`setup_linked_circuit`]) carries them in D and
+//! can be linked to the Pedersen commitments of π_{4,G1}.
+
+#[cfg(feature = "r1cs")]
+use crate::crypto::constraints::{
+    commit_leaf_gadget, enforce_bit_length, merkle_leaf_gadget, neighbour_leaf_gadget,
+    G2EncodingVar, MerklePathVar, PoseidonHashGadget,
+};
+#[cfg(feature = "r1cs")]
+use crate::crypto::encoding::G2_ENCODING_LEN;
+use crate::crypto::merkle::{
+    commit_leaf, cumulative_weights, merkle_leaf, neighbour_leaf, MerklePath, MerkleTree,
+    LEAF_VALUES,
+};
+#[cfg(feature = "r1cs")]
+use crate::crypto::merkle::{leaf_values, MERKLE_DEPTH, NEIGHBOUR_DEPTH};
+use crate::crypto::pedersen::{G1PedersenParams, PedersenOpening};
+use crate::crypto::poseidon::PoseidonHash;
+#[cfg(feature = "r1cs")]
+use crate::crypto::prf::ROUTING_VALUE_BITS;
+use crate::proving::commitment_link::{create_linked_proof, verify_linked_proof, LinkedProof};
+use crate::proving::groth16::{
+    prepare_verifying_key, CommitCarryingProvingKey, CommitCarryingVerifyingKey, Groth16,
+    Proof as Groth16Proof, ProvingKey, VerifyingKey,
+};
+use crate::proving::transcript::Transcript;
+use crate::types::{G1Point, PairingEngine, ProtocolError, ProtocolResult, PublicKey, ScalarField};
+#[cfg(feature = "r1cs")]
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+};
+#[cfg(feature = "r1cs")]
+use ark_relations::gr1cs::ConstraintSystemRef;
+use ark_relations::gr1cs::{ConstraintSynthesizer, SynthesisError};
+use ark_snark::SNARK;
+use rand::{Rng, RngCore};
+
+pub(crate) fn synthesis_error(e: SynthesisError) -> ProtocolError {
+    ProtocolError::CryptoError(format!("Groth16: {}", e))
+}
+
+/// Enforce c_1 = Hash(Hash(pk_s, md_{2,k_s}), r_1)
+///
+/// Reopens the π_1 leaf commitment, so a neighbour tree root used by π_2 or
+/// π_3 is the md_2 of the sender proven to be registered.
+#[cfg(feature = "r1cs")]
+fn enforce_sender_commitment(
+    gadget: &PoseidonHashGadget,
+    sender_pk: &G2EncodingVar,
+    neighbour_root: &FpVar<ScalarField>,
+    randomness: &FpVar<ScalarField>,
+    commitment: &FpVar<ScalarField>,
+) -> Result<(), SynthesisError> {
+    let leaf = merkle_leaf_gadget(gadget, sender_pk, neighbour_root)?;
+    commit_leaf_gadget(gadget, &leaf, randomness)?.enforce_equal(commitment)
+}
+
+/// Allocate the leaf values (encode_g2(pk), value) as witnesses
+///
+/// Call it before allocating any other witness: the commit-carrying key of
+/// [`setup_linked_circuit`] commits to the first witness variables, and the
+/// shape checks of the encoding allocate bits of their own.
+#[cfg(feature = "r1cs")]
+fn leaf_values_var(
+    cs: ConstraintSystemRef<ScalarField>,
+    pk: Option<&PublicKey>,
+    value: Option<ScalarField>,
+) -> Result<(G2EncodingVar, FpVar<ScalarField>), SynthesisError> {
+    let values = pk.zip(value).map(|(pk, value)| leaf_values(pk, value));
+    let elements = (0..LEAF_VALUES)
+        .map(|i| {
+            FpVar::new_witness(cs.clone(), || {
+                values
+                    .as_ref()
+                    .map(|values| values[i])
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
 
-use crate::types::ProtocolResult;
+    let pk = G2EncodingVar::from_field_elements(&elements[..G2_ENCODING_LEN])?;
+    Ok((pk, elements[G2_ENCODING_LEN].clone()))
+}
+
+/// Generate circuit-specific Groth16 keys
+pub fn setup_circuit<C: ConstraintSynthesizer<ScalarField>, R: RngCore>(
+    circuit: C,
+    rng: &mut R,
+) -> ProtocolResult<(ProvingKey<PairingEngine>, VerifyingKey<PairingEngine>)> {
+    Groth16::<PairingEngine>::circuit_specific_setup(circuit, rng).map_err(synthesis_error)
+}
+
+/// Prove an assigned circuit
+pub fn prove_circuit<C: ConstraintSynthesizer<ScalarField>, R: RngCore>(
+    pk: &ProvingKey<PairingEngine>,
+    circuit: C,
+    rng: &mut R,
+) -> ProtocolResult<Groth16Proof<PairingEngine>> {
+    Groth16::<PairingEngine>::prove(pk, circuit, rng).map_err(synthesis_error)
+}
+
+/// Verify a proof against its public inputs
+pub fn verify_circuit(
+    vk: &VerifyingKey<PairingEngine>,
+    public_inputs: &[ScalarField],
+    proof: &Groth16Proof<PairingEngine>,
+) -> ProtocolResult<bool> {
+    Groth16::<PairingEngine>::verify_proof(&prepare_verifying_key(vk), proof, public_inputs)
+        .map_err(synthesis_error)
+}
+
+/// Generate commit-carrying Groth16 keys whose D commits to the first
+/// `commit_witness_count` witnesses, e.g. the [`LEAF_VALUES`] leaf values
+/// of a membership circuit
+pub fn setup_linked_circuit<C: ConstraintSynthesizer<ScalarField>, R: Rng>(
+    circuit: C,
+    commit_witness_count: usize,
+    rng: &mut R,
+) -> ProtocolResult<CommitCarryingProvingKey<PairingEngine>> {
+    Groth16::<PairingEngine>::generate_random_parameters_with_commitment(
+        circuit,
+        commit_witness_count,
+        rng,
+    )
+    .map_err(synthesis_error)
+}
+
+/// Prove an assigned circuit and link its D to the Pedersen `commitment`
+///
+/// `opening` opens `commitment` under `params` to the committed witnesses.
+pub fn prove_linked_circuit<C, T, R>(
+    pk: &CommitCarryingProvingKey<PairingEngine>,
+    circuit: C,
+    params: &G1PedersenParams,
+    (commitment, opening): (&G1Point, &PedersenOpening),
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<LinkedProof>
+where
+    C: ConstraintSynthesizer<ScalarField>,
+    T: Transcript,
+    R: Rng,
+{
+    create_linked_proof(circuit, pk, params, (commitment, opening), transcript, rng)
+}
+
+/// Verify a linked proof against its public inputs and the Pedersen
+/// `commitment`
+pub fn verify_linked_circuit<T: Transcript>(
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    public_inputs: &[ScalarField],
+    commitment: &G1Point,
+    proof: &LinkedProof,
+    transcript: &mut T,
+) -> bool {
+    verify_linked_proof(
+        &prepare_verifying_key(&vk.vk),
+        vk,
+        params,
+        public_inputs,
+        commitment,
+        proof,
+        transcript,
+    )
+}
 
 /// Circuit for π_1: Sender public key membership
+///
+/// Shows (pk, md_{2,k_s}) ∈ MT(md_1) without revealing k_s:
+///
+/// - public: the key tree root md_1 and c_1 = Hash(leaf, r_1)
+/// - witness: pk, md_{2,k_s}, the authentication path and r_1
+///
+/// c_1 ties the leaf the path opens to π_2 and π_3, which reopen it. The
+/// leaf values (encode_g2(pk), md_{2,k_s}) are the first
+/// [`Self::COMMITTED_WITNESSES`] witnesses. With [`setup_linked_circuit`]
+/// the proof verifies with respect to the Pedersen commitment C_1 of
+/// π_{4,G1} ([`crate::proving::bridging`]).
+#[derive(Clone, Debug, Default)]
 pub struct SenderMembershipCircuit {
-    // TODO: Define circuit constraints
+    /// Root md_1 of the key tree
+    pub root: Option<ScalarField>,
+    /// c_1 = Hash(leaf, r_1)
+    pub leaf_commitment: Option<ScalarField>,
+    pub pk: Option<PublicKey>,
+    /// Metadata digest md_{2,k_s} of the sender
+    pub md: Option<ScalarField>,
+    pub path: Option<MerklePath>,
+    /// Blinding r_1 of c_1
+    pub randomness: Option<ScalarField>,
 }
 
 impl SenderMembershipCircuit {
+    /// Number of leading witnesses a commit-carrying key commits to
+    pub const COMMITTED_WITNESSES: usize = LEAF_VALUES;
+
+    /// Circuit without assignment, for the setup
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
-    pub fn generate_constraints(&self) -> ProtocolResult<()> {
-        // TODO: Generate R1CS constraints
+    /// Assigned circuit for the sender at leaf `index` of `tree`
+    pub fn with_witness(
+        hasher: &PoseidonHash,
+        tree: &MerkleTree,
+        pk: &PublicKey,
+        md: ScalarField,
+        index: usize,
+        randomness: ScalarField,
+    ) -> ProtocolResult<Self> {
+        let leaf = merkle_leaf(hasher, pk, &md);
+        if tree.leaf(index) != Some(leaf) {
+            return Err(ProtocolError::CryptoError(format!(
+                "(pk, md) is not leaf {} of the key tree",
+                index
+            )));
+        }
+
+        Ok(Self {
+            root: Some(tree.root()),
+            leaf_commitment: Some(commit_leaf(hasher, &leaf, &randomness)),
+            pk: Some(pk.clone()),
+            md: Some(md),
+            path: Some(tree.path(index)?),
+            randomness: Some(randomness),
+        })
+    }
+
+    /// Public inputs (md_1, c_1) in allocation order
+    pub fn public_inputs(root: ScalarField, leaf_commitment: ScalarField) -> Vec<ScalarField> {
+        vec![root, leaf_commitment]
+    }
+}
+
+#[cfg(feature = "r1cs")]
+impl ConstraintSynthesizer<ScalarField> for SenderMembershipCircuit {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ScalarField>,
+    ) -> Result<(), SynthesisError> {
+        let gadget = PoseidonHashGadget::from_native(&PoseidonHash::new());
+        let missing = || SynthesisError::AssignmentMissing;
+
+        let root = FpVar::new_input(cs.clone(), || self.root.ok_or_else(missing))?;
+        let leaf_commitment =
+            FpVar::new_input(cs.clone(), || self.leaf_commitment.ok_or_else(missing))?;
+
+        // Committed witnesses come first
+        let (pk, md) = leaf_values_var(cs.clone(), self.pk.as_ref(), self.md)?;
+        let randomness = FpVar::new_witness(cs.clone(), || self.randomness.ok_or_else(missing))?;
+        let path = MerklePathVar::new_witness(cs.clone(), MERKLE_DEPTH, self.path.as_ref())?;
+
+        let leaf = merkle_leaf_gadget(&gadget, &pk, &md)?;
+        path.compute_root(&gadget, &leaf)?.enforce_equal(&root)?;
+        commit_leaf_gadget(&gadget, &leaf, &randomness)?.enforce_equal(&leaf_commitment)?;
+
         Ok(())
     }
 }
 
 /// Circuit for π_2: Weight sub-tree proof
+///
+/// Shows that the receiver committed in c_3 is the neighbour ρ routes to:
+/// ρ lies in its weight bucket [v_1, v_2) of the sender's neighbour tree.
+///
+/// - public: ρ_{ν+1} (recomputed by the verifier from φ_{ν+1}), c_1 from π_1
+///   and c_3 from π_3
+/// - witness: pk_s, md_{2,k_s}, r_1, the receiver leaf (pk_r, v_2) with its
+///   path, the pre-receiver leaf (pk_pr, v_1) with its path, and r_3
+///
+/// The pre-receiver is the leaf right before the receiver, so v_1 is the
+/// previous cumulative weight; for the first neighbour v_1 = 0 and the
+/// pre-receiver opening is ignored. Both v_1 ≤ ρ and ρ < v_2 are shown by
+/// [`ROUTING_VALUE_BITS`]-bit range checks on ρ - v_1 and v_2 - ρ - 1.
+#[derive(Clone, Debug, Default)]
 pub struct WeightSubtreeCircuit {
-    // TODO: Define circuit constraints
+    /// Routing value ρ_{ν+1}
+    pub rho: Option<u32>,
+    /// c_1, shared with π_1
+    pub sender_commitment: Option<ScalarField>,
+    /// c_3, shared with π_3
+    pub receiver_commitment: Option<ScalarField>,
+    pub sender_pk: Option<PublicKey>,
+    /// Neighbour tree root md_{2,k_s}
+    pub neighbour_root: Option<ScalarField>,
+    /// Blinding r_1 of c_1
+    pub sender_randomness: Option<ScalarField>,
+    pub receiver_pk: Option<PublicKey>,
+    /// Upper end v_2 of the receiver's bucket
+    pub bound: Option<u64>,
+    pub path: Option<MerklePath>,
+    pub pre_receiver_pk: Option<PublicKey>,
+    /// Upper end v_1 of the pre-receiver's bucket
+    pub pre_bound: Option<u64>,
+    pub pre_path: Option<MerklePath>,
+    /// Blinding r_3 of c_3
+    pub receiver_randomness: Option<ScalarField>,
 }
 
 impl WeightSubtreeCircuit {
+    /// Circuit without assignment, for the setup
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Assigned circuit for the neighbour `rho` selects from the sender's
+    /// routing list `neighbours`
+    pub fn with_witness(
+        hasher: &PoseidonHash,
+        sender_pk: &PublicKey,
+        sender_randomness: ScalarField,
+        neighbours: &[(PublicKey, u32)],
+        rho: u32,
+        receiver_randomness: ScalarField,
+    ) -> ProtocolResult<Self> {
+        let bounds = cumulative_weights(neighbours.iter().map(|(_, weight)| *weight));
+        let position = bounds
+            .iter()
+            .position(|bound| (rho as u64) < *bound)
+            .ok_or_else(|| {
+                ProtocolError::CryptoError(format!(
+                    "routing value {} is not covered by the neighbour weights",
+                    rho
+                ))
+            })?;
+        // The first neighbour has no pre-receiver; open itself instead
+        let pre_position = position.saturating_sub(1);
+
+        let tree = MerkleTree::from_neighbours(hasher, neighbours)?;
+        let sender_leaf = merkle_leaf(hasher, sender_pk, &tree.root());
+        let receiver_pk = &neighbours[position].0;
+        let receiver_leaf = neighbour_leaf(hasher, receiver_pk, bounds[position]);
+
+        Ok(Self {
+            rho: Some(rho),
+            sender_commitment: Some(commit_leaf(hasher, &sender_leaf, &sender_randomness)),
+            receiver_commitment: Some(commit_leaf(hasher, &receiver_leaf, &receiver_randomness)),
+            sender_pk: Some(sender_pk.clone()),
+            neighbour_root: Some(tree.root()),
+            sender_randomness: Some(sender_randomness),
+            receiver_pk: Some(receiver_pk.clone()),
+            bound: Some(bounds[position]),
+            path: Some(tree.path(position)?),
+            pre_receiver_pk: Some(neighbours[pre_position].0.clone()),
+            pre_bound: Some(bounds[pre_position]),
+            pre_path: Some(tree.path(pre_position)?),
+            receiver_randomness: Some(receiver_randomness),
+        })
+    }
+
+    /// Public inputs (ρ, c_1, c_3) in allocation order
+    pub fn public_inputs(
+        rho: u32,
+        sender_commitment: ScalarField,
+        receiver_commitment: ScalarField,
+    ) -> Vec<ScalarField> {
+        vec![
+            ScalarField::from(rho as u64),
+            sender_commitment,
+            receiver_commitment,
+        ]
     }
+}
+
+#[cfg(feature = "r1cs")]
+impl ConstraintSynthesizer<ScalarField> for WeightSubtreeCircuit {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ScalarField>,
+    ) -> Result<(), SynthesisError> {
+        let gadget = PoseidonHashGadget::from_native(&PoseidonHash::new());
+        let missing = || SynthesisError::AssignmentMissing;
+        let pk_var = |pk: &Option<PublicKey>| {
+            G2EncodingVar::new_witness(cs.clone(), || {
+                pk.as_ref().map(|pk| pk.pk).ok_or_else(missing)
+            })
+        };
+        let bound_var = |bound: Option<u64>| {
+            FpVar::new_witness(cs.clone(), || {
+                bound.map(ScalarField::from).ok_or_else(missing)
+            })
+        };
+
+        let rho = FpVar::new_input(cs.clone(), || {
+            self.rho
+                .map(|rho| ScalarField::from(rho as u64))
+                .ok_or_else(missing)
+        })?;
+        let sender_commitment =
+            FpVar::new_input(cs.clone(), || self.sender_commitment.ok_or_else(missing))?;
+        let receiver_commitment =
+            FpVar::new_input(cs.clone(), || self.receiver_commitment.ok_or_else(missing))?;
+
+        let sender_pk = pk_var(&self.sender_pk)?;
+        let neighbour_root =
+            FpVar::new_witness(cs.clone(), || self.neighbour_root.ok_or_else(missing))?;
+        let sender_randomness =
+            FpVar::new_witness(cs.clone(), || self.sender_randomness.ok_or_else(missing))?;
+        let receiver_pk = pk_var(&self.receiver_pk)?;
+        let bound = bound_var(self.bound)?;
+        let path = MerklePathVar::new_witness(cs.clone(), NEIGHBOUR_DEPTH, self.path.as_ref())?;
+        let pre_receiver_pk = pk_var(&self.pre_receiver_pk)?;
+        let pre_bound = bound_var(self.pre_bound)?;
+        let pre_path =
+            MerklePathVar::new_witness(cs.clone(), NEIGHBOUR_DEPTH, self.pre_path.as_ref())?;
+        let receiver_randomness =
+            FpVar::new_witness(cs.clone(), || self.receiver_randomness.ok_or_else(missing))?;
+
+        enforce_sender_commitment(
+            &gadget,
+            &sender_pk,
+            &neighbour_root,
+            &sender_randomness,
+            &sender_commitment,
+        )?;
+
+        // Both openings are in the sender's neighbour tree
+        let receiver_leaf = neighbour_leaf_gadget(&gadget, &receiver_pk, &bound)?;
+        path.compute_root(&gadget, &receiver_leaf)?
+            .enforce_equal(&neighbour_root)?;
+        let pre_receiver_leaf = neighbour_leaf_gadget(&gadget, &pre_receiver_pk, &pre_bound)?;
+        pre_path
+            .compute_root(&gadget, &pre_receiver_leaf)?
+            .enforce_equal(&neighbour_root)?;
+
+        // The receiver is the one committed in c_3
+        commit_leaf_gadget(&gadget, &receiver_leaf, &receiver_randomness)?
+            .enforce_equal(&receiver_commitment)?;
+
+        // The pre-receiver is the previous leaf, unless the receiver is first
+        let index = path.index();
+        let is_first = index.is_eq(&FpVar::zero())?;
+        (pre_path.index() + FpVar::one()).conditional_enforce_equal(&index, &!&is_first)?;
+        let lower = is_first.select(&FpVar::zero(), &pre_bound)?;
+
+        // v_1 ≤ ρ < v_2
+        enforce_bit_length(&(&rho - &lower), ROUTING_VALUE_BITS)?;
+        enforce_bit_length(&(&bound - &rho - FpVar::one()), ROUTING_VALUE_BITS)?;
 
-    pub fn generate_constraints(&self) -> ProtocolResult<()> {
-        // TODO: Generate R1CS constraints for:
-        // - Merkle tree openings for receiver and pre-receiver
-        // - Range proof: v_1 < ρ ≤ v_2
         Ok(())
     }
 }
 
 /// Circuit for π_3: Receiver public key membership
+///
+/// Shows that pk_r is a leaf of the neighbour tree MT(md_{2,k_s}) of the
+/// sender committed in π_1, without revealing k_s or the receiver position:
+///
+/// - public: c_1 = Hash(Hash(pk_s, md_{2,k_s}), r_1) from π_1 and
+///   c_3 = Hash(Hash(pk_r, v_2), r_3)
+/// - witness: pk_s, md_{2,k_s}, r_1, pk_r, its bucket bound v_2, the path in
+///   the neighbour tree and r_3
+///
+/// Reopening c_1 ties the neighbour tree to the key tree leaf proven in π_1,
+/// and c_3 is the receiver commitment π_2 refers to.
+///
+/// The receiver leaf values (encode_g2(pk_r), v_2) are the first
+/// [`Self::COMMITTED_WITNESSES`] witnesses. With [`setup_linked_circuit`]
+/// the proof verifies with respect to the Pedersen commitment C_3 of
+/// π_{4,G1} ([`crate::proving::bridging`]).
+#[derive(Clone, Debug, Default)]
 pub struct ReceiverMembershipCircuit {
-    // TODO: Define circuit constraints
+    /// c_1, shared with π_1
+    pub sender_commitment: Option<ScalarField>,
+    /// c_3 = Hash(neighbour leaf, r_3)
+    pub receiver_commitment: Option<ScalarField>,
+    pub sender_pk: Option<PublicKey>,
+    /// Neighbour tree root md_{2,k_s}
+    pub neighbour_root: Option<ScalarField>,
+    /// Blinding r_1 of c_1
+    pub sender_randomness: Option<ScalarField>,
+    pub receiver_pk: Option<PublicKey>,
+    /// Upper end v_2 of the receiver's weight bucket
+    pub bound: Option<u64>,
+    pub path: Option<MerklePath>,
+    /// Blinding r_3 of c_3
+    pub receiver_randomness: Option<ScalarField>,
 }
 
 impl ReceiverMembershipCircuit {
+    /// Number of leading witnesses a commit-carrying key commits to
+    pub const COMMITTED_WITNESSES: usize = LEAF_VALUES;
+
+    /// Circuit without assignment, for the setup
     pub fn new() -> Self {
-        Self {}
+        Self::default()
     }
 
-    pub fn generate_constraints(&self) -> ProtocolResult<()> {
-        // TODO: Generate R1CS constraints
-        Ok(())
-    }
-}
+    /// Assigned circuit for the neighbour at `position` of the sender's
+    /// routing list `neighbours`
+    pub fn with_witness(
+        hasher: &PoseidonHash,
+        sender_pk: &PublicKey,
+        sender_randomness: ScalarField,
+        neighbours: &[(PublicKey, u32)],
+        position: usize,
+        receiver_randomness: ScalarField,
+    ) -> ProtocolResult<Self> {
+        let (receiver_pk, _) = neighbours.get(position).ok_or_else(|| {
+            ProtocolError::CryptoError(format!(
+                "receiver position {} outside a list of {} neighbours",
+                position,
+                neighbours.len()
+            ))
+        })?;
+        let bound = cumulative_weights(neighbours.iter().map(|(_, weight)| *weight))[position];
 
-/// Circuit for π_{4,G1}: Schnorr bridging in G1
-pub struct SchnorrG1Circuit {
-    // TODO: Define circuit constraints for:
-    // - Blinded public key relations
-    // - Commitment openings
-    // - Range proof v_1 < ρ ≤ v_2
-}
+        let tree = MerkleTree::from_neighbours(hasher, neighbours)?;
+        let sender_leaf = merkle_leaf(hasher, sender_pk, &tree.root());
+        let receiver_leaf = neighbour_leaf(hasher, receiver_pk, bound);
 
-impl SchnorrG1Circuit {
-    pub fn new() -> Self {
-        Self {}
+        Ok(Self {
+            sender_commitment: Some(commit_leaf(hasher, &sender_leaf, &sender_randomness)),
+            receiver_commitment: Some(commit_leaf(hasher, &receiver_leaf, &receiver_randomness)),
+            sender_pk: Some(sender_pk.clone()),
+            neighbour_root: Some(tree.root()),
+            sender_randomness: Some(sender_randomness),
+            receiver_pk: Some(receiver_pk.clone()),
+            bound: Some(bound),
+            path: Some(tree.path(position)?),
+            receiver_randomness: Some(receiver_randomness),
+        })
     }
 
-    pub fn generate_constraints(&self) -> ProtocolResult<()> {
-        // TODO: Generate constraints
-        Ok(())
+    /// Public inputs (c_1, c_3) in allocation order
+    pub fn public_inputs(
+        sender_commitment: ScalarField,
+        receiver_commitment: ScalarField,
+    ) -> Vec<ScalarField> {
+        vec![sender_commitment, receiver_commitment]
     }
 }
 
-/// Circuit for π_{4,G2}: Public key operations in G2
-pub struct PublicKeyOpsCircuit {
-    // TODO: Define circuit constraints for:
-    // - Ownership proof: pk* = G^sk · H^r
-    // - Valid diversified pk: (ppk_{s,2})^sk = ppk_{s,1}
-    // - Diversify correctness: ppk_r = (pk_r^d, G^d)
-    // - PRF correctness: φ^{(sk+θ)} = H
-}
+#[cfg(feature = "r1cs")]
+impl ConstraintSynthesizer<ScalarField> for ReceiverMembershipCircuit {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ScalarField>,
+    ) -> Result<(), SynthesisError> {
+        let gadget = PoseidonHashGadget::from_native(&PoseidonHash::new());
+        let missing = || SynthesisError::AssignmentMissing;
 
-impl PublicKeyOpsCircuit {
-    pub fn new() -> Self {
-        Self {}
-    }
+        let sender_commitment =
+            FpVar::new_input(cs.clone(), || self.sender_commitment.ok_or_else(missing))?;
+        let receiver_commitment =
+            FpVar::new_input(cs.clone(), || self.receiver_commitment.ok_or_else(missing))?;
+
+        // Committed witnesses come first
+        let (receiver_pk, bound) = leaf_values_var(
+            cs.clone(),
+            self.receiver_pk.as_ref(),
+            self.bound.map(ScalarField::from),
+        )?;
+        let sender_pk = G2EncodingVar::new_witness(cs.clone(), || {
+            self.sender_pk.as_ref().map(|pk| pk.pk).ok_or_else(missing)
+        })?;
+        let neighbour_root =
+            FpVar::new_witness(cs.clone(), || self.neighbour_root.ok_or_else(missing))?;
+        let sender_randomness =
+            FpVar::new_witness(cs.clone(), || self.sender_randomness.ok_or_else(missing))?;
+        let receiver_randomness =
+            FpVar::new_witness(cs.clone(), || self.receiver_randomness.ok_or_else(missing))?;
+        let path = MerklePathVar::new_witness(cs.clone(), NEIGHBOUR_DEPTH, self.path.as_ref())?;
+
+        // The neighbour tree is the one the π_1 leaf commits to
+        enforce_sender_commitment(
+            &gadget,
+            &sender_pk,
+            &neighbour_root,
+            &sender_randomness,
+            &sender_commitment,
+        )?;
+
+        let receiver_leaf = neighbour_leaf_gadget(&gadget, &receiver_pk, &bound)?;
+        path.compute_root(&gadget, &receiver_leaf)?
+            .enforce_equal(&neighbour_root)?;
+        commit_leaf_gadget(&gadget, &receiver_leaf, &receiver_randomness)?
+            .enforce_equal(&receiver_commitment)?;
 
-    pub fn generate_constraints(&self) -> ProtocolResult<()> {
-        // TODO: Generate constraints
         Ok(())
     }
 }
@@ -103,29 +605,406 @@ pub struct ForwardCircuit {
     pub sender_membership: SenderMembershipCircuit,
     pub weight_subtree: WeightSubtreeCircuit,
     pub receiver_membership: ReceiverMembershipCircuit,
-    pub schnorr_g1: SchnorrG1Circuit,
-    pub pubkey_ops: PublicKeyOpsCircuit,
 }
 
 impl ForwardCircuit {
     pub fn new() -> Self {
         Self {
-            sender_membership: SenderMembershipCircuit::new(),
-            weight_subtree: WeightSubtreeCircuit::new(),
-            receiver_membership: ReceiverMembershipCircuit::new(),
-            schnorr_g1: SchnorrG1Circuit::new(),
-            pubkey_ops: PublicKeyOpsCircuit::new(),
+            sender_membership: SenderMembershipCircuit::default(),
+            weight_subtree: WeightSubtreeCircuit::default(),
+            receiver_membership: ReceiverMembershipCircuit::default(),
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "r1cs"))]
 mod tests {
     use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::generators::protocol_generators;
+    use crate::crypto::merkle::test_utils::key_tree;
+    use crate::proving::bridging::leaf_commitment_params;
+    use crate::proving::transcript::Sha256Transcript;
+    use crate::WEIGHT_SUM;
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    fn is_satisfied<C: ConstraintSynthesizer<ScalarField>>(circuit: C) -> bool {
+        let cs = ConstraintSystem::<ScalarField>::new_ref();
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
 
     #[test]
     fn test_circuit_creation() {
         let _circuit = ForwardCircuit::new();
         // Just test construction
     }
+
+    #[test]
+    fn test_sender_membership_end_to_end() {
+        let mut rng = thread_rng();
+        let (hasher, pks, metadata, tree) = key_tree(6);
+        let params = leaf_commitment_params(protocol_generators()).unwrap();
+        let pk = setup_linked_circuit(
+            SenderMembershipCircuit::new(),
+            SenderMembershipCircuit::COMMITTED_WITNESSES,
+            &mut rng,
+        )
+        .unwrap();
+
+        let k_s = 4;
+        let r_1 = ScalarField::rand(&mut rng);
+        let circuit = SenderMembershipCircuit::with_witness(
+            &hasher,
+            &tree,
+            &pks[k_s],
+            metadata[k_s],
+            k_s,
+            r_1,
+        )
+        .unwrap();
+        let c_1 = circuit.leaf_commitment.unwrap();
+
+        // The Pedersen C_1 of π_{4,G1} for the key tree leaf (pk, md)
+        let (pedersen_c_1, opening) = params
+            .commit_random(&leaf_values(&pks[k_s], metadata[k_s]), &mut rng)
+            .unwrap();
+        let proof = prove_linked_circuit(
+            &pk,
+            circuit,
+            &params,
+            (&pedersen_c_1.point, &opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        let verify = |inputs: &[ScalarField], commitment: &G1Point| {
+            verify_linked_circuit(
+                &pk.vk,
+                &params,
+                inputs,
+                commitment,
+                &proof,
+                &mut Sha256Transcript::new(b"test"),
+            )
+        };
+
+        let inputs = SenderMembershipCircuit::public_inputs(tree.root(), c_1);
+        assert!(verify(&inputs, &pedersen_c_1.point));
+
+        // Another root or another leaf commitment
+        let other_root = SenderMembershipCircuit::public_inputs(c_1, c_1);
+        assert!(!verify(&other_root, &pedersen_c_1.point));
+        let other_commitment =
+            SenderMembershipCircuit::public_inputs(tree.root(), c_1 + ScalarField::from(1u64));
+        assert!(!verify(&other_commitment, &pedersen_c_1.point));
+
+        // A Pedersen C_1 to the leaf of another registered key
+        let (other_c_1, _) = params
+            .commit_random(&leaf_values(&pks[1], metadata[1]), &mut rng)
+            .unwrap();
+        assert!(!verify(&inputs, &other_c_1.point));
+    }
+
+    #[test]
+    fn test_sender_membership_rejects_unregistered_key() {
+        let mut rng = thread_rng();
+        let (hasher, pks, metadata, tree) = key_tree(4);
+        let (_, outsider) = keygen(&mut rng);
+        let r_1 = ScalarField::rand(&mut rng);
+
+        let honest =
+            SenderMembershipCircuit::with_witness(&hasher, &tree, &pks[1], metadata[1], 1, r_1)
+                .unwrap();
+        assert!(is_satisfied(honest.clone()));
+        assert!(SenderMembershipCircuit::with_witness(
+            &hasher,
+            &tree,
+            &outsider,
+            metadata[1],
+            1,
+            r_1
+        )
+        .is_err());
+
+        // An outsider reusing the path of leaf 1
+        let mut forged = honest.clone();
+        forged.pk = Some(outsider);
+        assert!(!is_satisfied(forged));
+
+        // The right key with another metadata digest
+        let mut forged = honest.clone();
+        forged.md = Some(metadata[2]);
+        assert!(!is_satisfied(forged));
+
+        // A leaf commitment to a different leaf
+        let mut forged = honest;
+        forged.leaf_commitment = Some(commit_leaf(&hasher, &tree.leaf(2).unwrap(), &r_1));
+        assert!(!is_satisfied(forged));
+    }
+
+    fn neighbour_list(n: usize) -> Vec<(PublicKey, u32)> {
+        let mut rng = thread_rng();
+        let weight = (WEIGHT_SUM / n as u64) as u32;
+        (0..n).map(|_| (keygen(&mut rng).1, weight)).collect()
+    }
+
+    #[test]
+    fn test_weight_subtree_end_to_end() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let neighbours = neighbour_list(4);
+        let (pk, vk) = setup_circuit(WeightSubtreeCircuit::new(), &mut rng).unwrap();
+
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+        // Third bucket [2^31, 3 · 2^30)
+        let rho = (1u32 << 31) + 5;
+        let circuit =
+            WeightSubtreeCircuit::with_witness(&hasher, &sender, r_1, &neighbours, rho, r_3)
+                .unwrap();
+        let c_1 = circuit.sender_commitment.unwrap();
+        let c_3 = circuit.receiver_commitment.unwrap();
+
+        // The same receiver commitment π_3 proves membership for
+        let receiver =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &neighbours, 2, r_3)
+                .unwrap();
+        assert_eq!(receiver.sender_commitment, Some(c_1));
+        assert_eq!(receiver.receiver_commitment, Some(c_3));
+
+        let proof = prove_circuit(&pk, circuit, &mut rng).unwrap();
+        let inputs = WeightSubtreeCircuit::public_inputs(rho, c_1, c_3);
+        assert!(verify_circuit(&vk, &inputs, &proof).unwrap());
+
+        // A routing value in another bucket
+        let inputs = WeightSubtreeCircuit::public_inputs(rho - (1 << 30), c_1, c_3);
+        assert!(!verify_circuit(&vk, &inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_weight_subtree_bucket_edges() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let neighbours = neighbour_list(4);
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+
+        // First and last value of the first, an inner and the last bucket
+        for rho in [0, (1 << 30) - 1, 1 << 30, (1 << 31) - 1, 3 << 30, u32::MAX] {
+            let circuit =
+                WeightSubtreeCircuit::with_witness(&hasher, &sender, r_1, &neighbours, rho, r_3)
+                    .unwrap();
+            assert!(is_satisfied(circuit), "ρ = {}", rho);
+        }
+
+        // Weights that do not cover ρ
+        let short = neighbour_list(4)[..3].to_vec();
+        assert!(
+            WeightSubtreeCircuit::with_witness(&hasher, &sender, r_1, &short, u32::MAX, r_3)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_weight_subtree_rejects_wrong_receiver() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let neighbours = neighbour_list(4);
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+        let rho = (1u32 << 31) + 5;
+
+        let honest =
+            WeightSubtreeCircuit::with_witness(&hasher, &sender, r_1, &neighbours, rho, r_3)
+                .unwrap();
+        assert!(is_satisfied(honest.clone()));
+
+        // The neighbour of the next bucket, opened honestly, for this ρ
+        let mut forged = WeightSubtreeCircuit::with_witness(
+            &hasher,
+            &sender,
+            r_1,
+            &neighbours,
+            rho + (1 << 30),
+            r_3,
+        )
+        .unwrap();
+        forged.rho = Some(rho);
+        assert!(!is_satisfied(forged));
+
+        // A pre-receiver two leaves back widens the bucket to [2^30, 3 · 2^30)
+        let mut forged = honest.clone();
+        let tree = MerkleTree::from_neighbours(&hasher, &neighbours).unwrap();
+        forged.pre_receiver_pk = Some(neighbours[0].0.clone());
+        forged.pre_bound = Some(1 << 30);
+        forged.pre_path = Some(tree.path(0).unwrap());
+        forged.rho = Some((1 << 31) - 1);
+        assert!(!is_satisfied(forged));
+
+        // A receiver commitment to another neighbour
+        let mut forged = honest;
+        let other = neighbour_leaf(&hasher, &neighbours[1].0, 1 << 31);
+        forged.receiver_commitment = Some(commit_leaf(&hasher, &other, &r_3));
+        assert!(!is_satisfied(forged));
+    }
+
+    #[test]
+    fn test_receiver_membership_end_to_end() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let neighbours = neighbour_list(4);
+        let (pk, vk) = setup_circuit(ReceiverMembershipCircuit::new(), &mut rng).unwrap();
+
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+        let circuit =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &neighbours, 2, r_3)
+                .unwrap();
+        let c_1 = circuit.sender_commitment.unwrap();
+        let c_3 = circuit.receiver_commitment.unwrap();
+
+        // c_1 is the leaf commitment π_1 proves for the same sender
+        let md_2 = MerkleTree::from_neighbours(&hasher, &neighbours)
+            .unwrap()
+            .root();
+        assert_eq!(
+            c_1,
+            commit_leaf(&hasher, &merkle_leaf(&hasher, &sender, &md_2), &r_1)
+        );
+
+        let proof = prove_circuit(&pk, circuit, &mut rng).unwrap();
+        let inputs = ReceiverMembershipCircuit::public_inputs(c_1, c_3);
+        assert!(verify_circuit(&vk, &inputs, &proof).unwrap());
+
+        let swapped = ReceiverMembershipCircuit::public_inputs(c_3, c_1);
+        assert!(!verify_circuit(&vk, &swapped, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_receiver_membership_linked_to_pedersen_commitment() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let neighbours = neighbour_list(4);
+        let params = leaf_commitment_params(protocol_generators()).unwrap();
+        let pk = setup_linked_circuit(
+            ReceiverMembershipCircuit::new(),
+            ReceiverMembershipCircuit::COMMITTED_WITNESSES,
+            &mut rng,
+        )
+        .unwrap();
+
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+        let circuit =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &neighbours, 2, r_3)
+                .unwrap();
+        let inputs = ReceiverMembershipCircuit::public_inputs(
+            circuit.sender_commitment.unwrap(),
+            circuit.receiver_commitment.unwrap(),
+        );
+
+        // C_3 of π_{4,G1} for the receiver leaf (pk_r, v_2)
+        let values = leaf_values(&neighbours[2].0, ScalarField::from(circuit.bound.unwrap()));
+        let (c_3, opening) = params.commit_random(&values, &mut rng).unwrap();
+        let proof = prove_linked_circuit(
+            &pk,
+            circuit,
+            &params,
+            (&c_3.point, &opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_linked_circuit(
+            &pk.vk,
+            &params,
+            &inputs,
+            &c_3.point,
+            &proof,
+            &mut Sha256Transcript::new(b"test")
+        ));
+
+        // C_3 for another neighbour
+        let other = leaf_values(&neighbours[1].0, ScalarField::from(1u64 << 31));
+        let (other_c_3, _) = params.commit_random(&other, &mut rng).unwrap();
+        assert!(!verify_linked_circuit(
+            &pk.vk,
+            &params,
+            &inputs,
+            &other_c_3.point,
+            &proof,
+            &mut Sha256Transcript::new(b"test")
+        ));
+
+        // The prover cannot link D to a commitment to other values
+        let circuit =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &neighbours, 2, r_3)
+                .unwrap();
+        let (other_c_3, other_opening) = params.commit_random(&other, &mut rng).unwrap();
+        let forged = prove_linked_circuit(
+            &pk,
+            circuit,
+            &params,
+            (&other_c_3.point, &other_opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        );
+        assert!(forged.is_err());
+    }
+
+    #[test]
+    fn test_receiver_membership_rejects_non_neighbour() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_, sender) = keygen(&mut rng);
+        let (_, outsider) = keygen(&mut rng);
+        let neighbours = neighbour_list(3);
+        let r_1 = ScalarField::rand(&mut rng);
+        let r_3 = ScalarField::rand(&mut rng);
+
+        let honest =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &neighbours, 1, r_3)
+                .unwrap();
+        assert!(is_satisfied(honest.clone()));
+        assert!(ReceiverMembershipCircuit::with_witness(
+            &hasher,
+            &sender,
+            r_1,
+            &neighbours,
+            3,
+            r_3
+        )
+        .is_err());
+
+        // An outsider reusing the path of position 1, committed honestly
+        let mut forged = honest.clone();
+        let outsider_leaf = neighbour_leaf(&hasher, &outsider, forged.bound.unwrap());
+        forged.receiver_commitment = Some(commit_leaf(&hasher, &outsider_leaf, &r_3));
+        forged.receiver_pk = Some(outsider.clone());
+        assert!(!is_satisfied(forged));
+
+        // The right receiver with another bucket bound
+        let mut forged = honest.clone();
+        forged.bound = Some(forged.bound.unwrap() + 1);
+        assert!(!is_satisfied(forged));
+
+        // A neighbour of another node: its list contains the outsider, but
+        // its root is not the one committed in c_1
+        let mut other_list = neighbours.clone();
+        other_list[1].0 = outsider;
+        let mut forged =
+            ReceiverMembershipCircuit::with_witness(&hasher, &sender, r_1, &other_list, 1, r_3)
+                .unwrap();
+        assert!(is_satisfied(forged.clone()));
+        forged.sender_commitment = honest.sender_commitment;
+        assert!(!is_satisfied(forged));
+    }
 }
@@ -0,0 +1,593 @@
+//! Linking commit-carrying Groth16 proofs to Pedersen commitments
+//!
+//! A [`crate::proving::groth16::CommitCarryingProof`] carries
+//!
+//! ```text
+//! D = Σ_j w_j · L_j + v · (η/γ) · G
+//! ```
+//!
+//! for the committed witnesses w_j of the circuit, with the bases L_j and
+//! (η/γ) · G of its verifying key. D is only meaningful to the pairing check,
+//! so the other proof components refer to a Pedersen commitment of their own
+//! choice, e.g. the commitment C_3 of π_{4,G1} to the receiver leaf values.
+//! The sigma proof here shows that D and C commit to the same w_j:
+//!
+//! ```text
+//! D = Σ_j w_j · L_j + v · (η/γ) · G
+//! C = Σ_j w_j · G_j + r · H
+//! ```
+//!
+//! with one response per w_j, which is how a Groth16 proof "verifies with
+//! respect to C". Like the range proofs it runs on a caller-supplied
+//! [`Transcript`], so it can be bound to the hop context.
+//!
+//! The link is also what binds the public inputs. D sits next to them on
+//! the γ side of the pairing check, so from a valid (π, D) for inputs x
+//! anyone gets D' = D + Σ_i (x_i − x'_i) · K_i for which the same π verifies
+//! with inputs x' (see [`crate::proving::groth16::malleability`]). D' is no
+//! longer in the span of the L_j and (η/γ) · G, so nobody can link it.
+//! [`verify_linked_proof`] therefore checks the pairing equation and the
+//! link together and is the entry point for commit-carrying proofs;
+//! [`LinkedProof`] carries both parts.
+//!
+//! Anyone can rerandomize (A, B, C) and keep D, C and the link. The link is a
+//! Fiat–Shamir proof over D and C, so it cannot be carried over to a fresh D
+//! or C without their openings: only their holder rerandomizes (π, D, C,
+//! link) together ([`rerandomize_linked_proof`]), so that the posted proof
+//! cannot be matched to the original.
+
+use crate::crypto::pedersen::{G1PedersenParams, PedersenCommitment, PedersenOpening};
+use crate::proving::circuits::synthesis_error;
+use crate::proving::groth16::{
+    CommitCarryingProof, CommitCarryingProvingKey, CommitCarryingVerifyingKey, ContextBoundProof,
+    Groth16, PreparedVerifyingKey,
+};
+use crate::proving::sigma::{LinearRelation, SigmaProof};
+use crate::proving::transcript::Transcript;
+use crate::types::{G1Point, PairingEngine, ProtocolError, ProtocolResult, ScalarField};
+use ark_ff::PrimeField;
+use ark_relations::gr1cs::ConstraintSynthesizer;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+
+/// A commit-carrying proof together with the link of its D to a Pedersen
+/// commitment
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkedProof {
+    pub proof: CommitCarryingProof<PairingEngine>,
+    /// Sigma proof that D and the commitment open to the same values
+    pub link: SigmaProof,
+}
+
+/// A context-bound commit-carrying proof together with the link of its D
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkedContextProof {
+    pub proof: ContextBoundProof<PairingEngine, CommitCarryingProof<PairingEngine>>,
+    /// Sigma proof that D and the commitment open to the same values, on a
+    /// transcript that has absorbed the context and the one-time key
+    pub link: SigmaProof,
+}
+
+/// The relation between D and C
+///
+/// Witnesses are the committed values w_j, then v, then r. C must use at
+/// least as many bases as the verifying key commits to.
+pub fn commitment_link_relation(
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    groth16_commitment: &G1Point,
+    commitment: &G1Point,
+) -> ProtocolResult<LinearRelation> {
+    let n = vk.commitment_bases.len();
+    if params.len() < n {
+        return Err(ProtocolError::CryptoError(format!(
+            "Groth16 proof commits to {} values, Pedersen parameters have {} bases",
+            n,
+            params.len()
+        )));
+    }
+
+    let (v, r) = (n, n + 1);
+    let mut d_terms: Vec<_> = vk.commitment_bases.iter().copied().enumerate().collect();
+    d_terms.push((v, vk.eta_gamma_inv_g1));
+    let mut c_terms: Vec<_> = params.bases[..n].iter().copied().enumerate().collect();
+    c_terms.push((r, params.blinding));
+
+    let mut relation = LinearRelation::new(n + 2);
+    relation
+        .add_g1(*groth16_commitment, d_terms)
+        .add_g1(*commitment, c_terms);
+    Ok(relation)
+}
+
+/// Prove that the D of a commit-carrying proof and `commitment` open to the
+/// same values
+///
+/// `opening` opens `commitment`; its values are the committed witnesses and
+/// `v` is the randomness returned by
+/// [`crate::proving::groth16::Groth16::create_random_proof_with_commitment`].
+pub fn prove_commitment_link<T: Transcript, R: Rng>(
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    (groth16_commitment, v): (&G1Point, &ScalarField),
+    (commitment, opening): (&G1Point, &PedersenOpening),
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<SigmaProof> {
+    if opening.values.len() != vk.commitment_bases.len() {
+        return Err(ProtocolError::CryptoError(format!(
+            "opening of {} values for a Groth16 commitment to {}",
+            opening.values.len(),
+            vk.commitment_bases.len()
+        )));
+    }
+
+    let relation = commitment_link_relation(vk, params, groth16_commitment, commitment)?;
+    let mut witnesses = opening.values.clone();
+    witnesses.push(*v);
+    witnesses.push(opening.randomness);
+    relation.prove(&witnesses, transcript, rng)
+}
+
+/// Verify a proof from [`prove_commitment_link`]
+///
+/// Only checks the link; [`verify_linked_proof`] also checks the
+/// commit-carrying proof, which does not bind its public inputs without it.
+pub fn verify_commitment_link<T: Transcript>(
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    groth16_commitment: &G1Point,
+    commitment: &G1Point,
+    proof: &SigmaProof,
+    transcript: &mut T,
+) -> bool {
+    commitment_link_relation(vk, params, groth16_commitment, commitment)
+        .is_ok_and(|relation| relation.verify(proof, transcript))
+}
+
+/// Prove `circuit` with the commit-carrying key `pk` and link its D to
+/// `commitment`
+///
+/// `opening` opens `commitment` to the committed witnesses of `circuit`.
+pub fn create_linked_proof<C, T, R>(
+    circuit: C,
+    pk: &CommitCarryingProvingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    (commitment, opening): (&G1Point, &PedersenOpening),
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<LinkedProof>
+where
+    C: ConstraintSynthesizer<ScalarField>,
+    T: Transcript,
+    R: Rng,
+{
+    let (proof, v) =
+        Groth16::<PairingEngine>::create_random_proof_with_commitment(circuit, pk, rng)
+            .map_err(synthesis_error)?;
+    let link = prove_commitment_link(
+        &pk.vk,
+        params,
+        (&proof.d, &v),
+        (commitment, opening),
+        transcript,
+        rng,
+    )?;
+    Ok(LinkedProof { proof, link })
+}
+
+/// Verify a commit-carrying proof for `public_inputs` together with the
+/// link of its D to `commitment`
+///
+/// `pvk` is the prepared `vk.vk`.
+pub fn verify_linked_proof<T: Transcript>(
+    pvk: &PreparedVerifyingKey<PairingEngine>,
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    public_inputs: &[ScalarField],
+    commitment: &G1Point,
+    proof: &LinkedProof,
+    transcript: &mut T,
+) -> bool {
+    Groth16::<PairingEngine>::verify_pairing_unlinked(pvk, &proof.proof, public_inputs)
+        .unwrap_or(false)
+        && verify_commitment_link(
+            vk,
+            params,
+            &proof.proof.d,
+            commitment,
+            &proof.link,
+            transcript,
+        )
+}
+
+/// Absorb the message context and the one-time key of a context-bound proof
+fn append_proof_context<T: Transcript>(transcript: &mut T, context: &[u8], ots_vk: &G1Point) {
+    let chunks: Vec<_> = context
+        .chunks(31)
+        .map(ScalarField::from_le_bytes_mod_order)
+        .collect();
+    transcript.append_u64(b"context_len", context.len() as u64);
+    transcript.append_field_elements(b"context", &chunks);
+    transcript.append_g1(b"ots_vk", ots_vk);
+}
+
+/// Prove `circuit` with the commit-carrying, context-bound key `pk` and link
+/// its D to `commitment`
+///
+/// The link is proven on `transcript` after it absorbs `context` and the
+/// one-time key, so it only verifies for this signed proof.
+pub fn create_linked_proof_with_context<C, T, R>(
+    circuit: C,
+    pk: &CommitCarryingProvingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    (commitment, opening): (&G1Point, &PedersenOpening),
+    context: &[u8],
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<LinkedContextProof>
+where
+    C: ConstraintSynthesizer<ScalarField>,
+    T: Transcript,
+    R: Rng,
+{
+    let (proof, v) = Groth16::<PairingEngine>::create_random_proof_with_commitment_and_context(
+        circuit, pk, context, rng,
+    )
+    .map_err(synthesis_error)?;
+    append_proof_context(transcript, context, &proof.ots_vk);
+    let link = prove_commitment_link(
+        &pk.vk,
+        params,
+        (&proof.proof.d, &v),
+        (commitment, opening),
+        transcript,
+        rng,
+    )?;
+    Ok(LinkedContextProof { proof, link })
+}
+
+/// Verify a context-bound commit-carrying proof for `public_inputs` and
+/// `context`: the one-time signature, the pairing check and the link of its
+/// D to `commitment`
+///
+/// `pvk` is the prepared `vk.vk`. This is the entry point for context-bound
+/// commit-carrying proofs, which bind neither their inputs nor their
+/// one-time key without the link.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_linked_proof_with_context<T: Transcript>(
+    pvk: &PreparedVerifyingKey<PairingEngine>,
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    public_inputs: &[ScalarField],
+    commitment: &G1Point,
+    context: &[u8],
+    proof: &LinkedContextProof,
+    transcript: &mut T,
+) -> bool {
+    if !Groth16::<PairingEngine>::verify_pairing_unlinked_with_context(
+        pvk,
+        &proof.proof,
+        public_inputs,
+        context,
+    )
+    .unwrap_or(false)
+    {
+        return false;
+    }
+
+    append_proof_context(transcript, context, &proof.proof.ots_vk);
+    verify_commitment_link(
+        vk,
+        params,
+        &proof.proof.proof.d,
+        commitment,
+        &proof.link,
+        transcript,
+    )
+}
+
+/// Rerandomize a commit-carrying proof, its D and `commitment` together and
+/// link them again
+///
+/// `v` opens D and `opening` opens `commitment`. Returns the linked proof,
+/// the opening v + v' of its D, the new commitment C + r' · H and its
+/// opening.
+pub fn rerandomize_linked_proof<T: Transcript, R: Rng>(
+    vk: &CommitCarryingVerifyingKey<PairingEngine>,
+    params: &G1PedersenParams,
+    (proof, v): (&CommitCarryingProof<PairingEngine>, &ScalarField),
+    (commitment, opening): (&G1Point, &PedersenOpening),
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<(LinkedProof, ScalarField, (G1Point, PedersenOpening))> {
+    let (proof, shift) =
+        Groth16::<PairingEngine>::rerandomize_proof_with_commitment(vk, proof, rng);
+    let v = *v + shift;
+    let (commitment, opening) =
+        params.rerandomize(&PedersenCommitment { point: *commitment }, opening, rng);
+
+    let link = prove_commitment_link(
+        vk,
+        params,
+        (&proof.d, &v),
+        (&commitment.point, &opening),
+        transcript,
+        rng,
+    )?;
+    Ok((LinkedProof { proof, link }, v, (commitment.point, opening)))
+}
+
+#[cfg(all(test, feature = "r1cs"))]
+mod tests {
+    use super::*;
+    use crate::crypto::generators::protocol_generators;
+    use crate::proving::groth16::{malleability::Transformation, prepare_verifying_key, Groth16};
+    use crate::proving::transcript::Sha256Transcript;
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_r1cs_std::{alloc::AllocVar, eq::EqGadget, fields::fp::FpVar};
+    use ark_relations::gr1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    /// Knowledge of (sk, md) with sk · md = x, committing to sk and md
+    #[derive(Clone, Default)]
+    struct ProductCircuit {
+        sk: Option<ScalarField>,
+        md: Option<ScalarField>,
+    }
+
+    impl ConstraintSynthesizer<ScalarField> for ProductCircuit {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<ScalarField>,
+        ) -> Result<(), SynthesisError> {
+            let missing = || SynthesisError::AssignmentMissing;
+            // Committed witnesses come first
+            let sk = FpVar::new_witness(cs.clone(), || self.sk.ok_or_else(missing))?;
+            let md = FpVar::new_witness(cs.clone(), || self.md.ok_or_else(missing))?;
+            let x = FpVar::new_input(cs.clone(), || {
+                Ok(self.sk.ok_or_else(missing)? * self.md.ok_or_else(missing)?)
+            })?;
+            (sk * md).enforce_equal(&x)
+        }
+    }
+
+    #[test]
+    fn test_commitment_link() {
+        let mut rng = thread_rng();
+        let params = G1PedersenParams::from_generators(protocol_generators(), 2).unwrap();
+        let pk = Groth16::<PairingEngine>::generate_random_parameters_with_commitment(
+            ProductCircuit::default(),
+            2,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&pk.vk.vk);
+
+        let sk = ScalarField::rand(&mut rng);
+        let md = ScalarField::rand(&mut rng);
+        let (proof, v) = Groth16::<PairingEngine>::create_random_proof_with_commitment(
+            ProductCircuit {
+                sk: Some(sk),
+                md: Some(md),
+            },
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(
+            Groth16::<PairingEngine>::verify_pairing_unlinked(&pvk, &proof, &[sk * md]).unwrap()
+        );
+
+        // A Pedersen commitment to the same sk and md
+        let (c_1, opening) = params.commit_random(&[sk, md], &mut rng).unwrap();
+        let link = prove_commitment_link(
+            &pk.vk,
+            &params,
+            (&proof.d, &v),
+            (&c_1.point, &opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_commitment_link(
+            &pk.vk,
+            &params,
+            &proof.d,
+            &c_1.point,
+            &link,
+            &mut Sha256Transcript::new(b"test")
+        ));
+        assert!(!verify_commitment_link(
+            &pk.vk,
+            &params,
+            &proof.d,
+            &c_1.point,
+            &link,
+            &mut Sha256Transcript::new(b"other")
+        ));
+
+        // A commitment to another md cannot be linked
+        let (c_other, other_opening) = params.commit_random(&[sk, md + sk], &mut rng).unwrap();
+        assert!(prove_commitment_link(
+            &pk.vk,
+            &params,
+            (&proof.d, &v),
+            (&c_other.point, &other_opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .is_err());
+        assert!(!verify_commitment_link(
+            &pk.vk,
+            &params,
+            &proof.d,
+            &c_other.point,
+            &link,
+            &mut Sha256Transcript::new(b"test")
+        ));
+
+        // Nor can the link be moved to the D of another proof
+        let (other_proof, _) = Groth16::<PairingEngine>::create_random_proof_with_commitment(
+            ProductCircuit {
+                sk: Some(sk),
+                md: Some(md),
+            },
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(!verify_commitment_link(
+            &pk.vk,
+            &params,
+            &other_proof.d,
+            &c_1.point,
+            &link,
+            &mut Sha256Transcript::new(b"test")
+        ));
+    }
+
+    #[test]
+    fn test_linked_proof_binds_inputs() {
+        let mut rng = thread_rng();
+        let params = G1PedersenParams::from_generators(protocol_generators(), 2).unwrap();
+        let pk = Groth16::<PairingEngine>::generate_random_parameters_with_commitment(
+            ProductCircuit::default(),
+            2,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&pk.vk.vk);
+
+        let sk = ScalarField::rand(&mut rng);
+        let md = ScalarField::rand(&mut rng);
+        let x = sk * md;
+        let (c_1, opening) = params.commit_random(&[sk, md], &mut rng).unwrap();
+        let proof = create_linked_proof(
+            ProductCircuit {
+                sk: Some(sk),
+                md: Some(md),
+            },
+            &pk,
+            &params,
+            (&c_1.point, &opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        let verify = |proof: &LinkedProof, x: ScalarField| {
+            verify_linked_proof(
+                &pvk,
+                &pk.vk,
+                &params,
+                &[x],
+                &c_1.point,
+                proof,
+                &mut Sha256Transcript::new(b"test"),
+            )
+        };
+        assert!(verify(&proof, x));
+        assert!(!verify(&proof, x + ScalarField::from(1u64)));
+
+        // Shifting the input moves D out of the span of the commitment bases:
+        // the pairing check passes, the link does not
+        let one = ScalarField::from(1u64);
+        let mut forged_proof = proof.proof.clone();
+        forged_proof.d =
+            (proof.proof.d.into_group() - pk.vk.vk.gamma_abc_g1[1] * one).into_affine();
+        assert!(
+            Groth16::<PairingEngine>::verify_pairing_unlinked(&pvk, &forged_proof, &[x + one])
+                .unwrap()
+        );
+        let forged = LinkedProof {
+            proof: forged_proof,
+            link: proof.link.clone(),
+        };
+        assert!(!verify(&forged, x + one));
+
+        // Rerandomizing keeps D and thereby the link
+        let rerandomize = Transformation::Rerandomize {
+            r1: ScalarField::rand(&mut rng),
+            r2: ScalarField::rand(&mut rng),
+        };
+        let rerandomized = Groth16::<PairingEngine>::transform_proof_with_commitment(
+            &pk.vk,
+            &proof.proof,
+            &rerandomize,
+        )
+        .unwrap();
+        assert_ne!(rerandomized, proof.proof);
+        let rerandomized = LinkedProof {
+            proof: rerandomized,
+            link: proof.link.clone(),
+        };
+        assert!(verify(&rerandomized, x));
+    }
+
+    #[test]
+    fn test_rerandomize_linked_proof() {
+        let mut rng = thread_rng();
+        let params = G1PedersenParams::from_generators(protocol_generators(), 2).unwrap();
+        let pk = Groth16::<PairingEngine>::generate_random_parameters_with_commitment(
+            ProductCircuit::default(),
+            2,
+            &mut rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key(&pk.vk.vk);
+
+        let sk = ScalarField::rand(&mut rng);
+        let md = ScalarField::rand(&mut rng);
+        let (c_1, opening) = params.commit_random(&[sk, md], &mut rng).unwrap();
+        let (proof, v) = Groth16::<PairingEngine>::create_random_proof_with_commitment(
+            ProductCircuit {
+                sk: Some(sk),
+                md: Some(md),
+            },
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+
+        let (linked, new_v, (new_c_1, new_opening)) = rerandomize_linked_proof(
+            &pk.vk,
+            &params,
+            (&proof, &v),
+            (&c_1.point, &opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        let verify = |proof: &LinkedProof, commitment: &G1Point| {
+            verify_linked_proof(
+                &pvk,
+                &pk.vk,
+                &params,
+                &[sk * md],
+                commitment,
+                proof,
+                &mut Sha256Transcript::new(b"test"),
+            )
+        };
+        assert!(verify(&linked, &new_c_1));
+        assert_ne!(linked.proof.d, proof.d);
+        assert_ne!(new_c_1, c_1.point);
+        assert_eq!(new_opening.values, opening.values);
+
+        // The new link does not verify against the old commitment
+        assert!(!verify(&linked, &c_1.point));
+
+        // The returned openings rerandomize again
+        let (again, _, (again_c_1, _)) = rerandomize_linked_proof(
+            &pk.vk,
+            &params,
+            (&linked.proof, &new_v),
+            (&new_c_1, &new_opening),
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify(&again, &again_c_1));
+    }
+}
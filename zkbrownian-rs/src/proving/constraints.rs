@@ -52,12 +52,6 @@ impl ConstraintSystem {
         // TODO: Implement multiplication constraint
         Ok(())
     }
-
-    /// Enforce range proof: value is in range [min, max]
-    pub fn enforce_range(&mut self, _value: usize, _min: u64, _max: u64) -> ProtocolResult<()> {
-        // TODO: Implement range constraint using bit decomposition
-        Ok(())
-    }
 }
 
 impl Default for ConstraintSystem {
@@ -0,0 +1,271 @@
+//! Context-bound, simulation-extractable Groth16 proofs.
+//!
+//! Anyone can rerandomize a plain Groth16 proof
+//! ([`Groth16::rerandomize_proof`]), so a relay could take the proof of
+//! another forwarder and post it under a different message context. The
+//! context-bound mode closes this with a one-time signature, in the style of
+//! the one-time-signature compilers for simulation-sound NIZKs
+//! ([Groth06](https://eprint.iacr.org/2006/309)):
+//!
+//! 1. the prover samples a one-time Schnorr key `(sk, ots_vk = sk * G)`;
+//! 2. the circuit gets one extra public input `H(ots_vk)`, after its own;
+//! 3. the prover signs `(ots_vk, proof, context)` with `sk`.
+//!
+//! Rerandomizing the proof or changing the context invalidates the
+//! signature, and re-signing under a fresh key changes the extra input, for
+//! which the proof does not verify. The public inputs need not be signed,
+//! the proof only verifies for its own. The extra input needs no
+//! constraint: under [`super::r1cs_to_qap::LibsnarkReduction`] every public
+//! input gets a constraint row of its own, which binds it in a plain proof.
+//!
+//! The commit-carrying variant signs `D` along with `(A, B, C)`, so a relay
+//! cannot reblind it either. Its pairing check does not bind the public
+//! inputs, though, and the binding is one of them: `D` can absorb the shift
+//! to the binding of a fresh key ([`super::malleability`]). Context-bound
+//! commit-carrying proofs are therefore only sound together with the link
+//! of `D`, proven on a transcript bound to the same context and one-time
+//! key; [`crate::proving::commitment_link::verify_linked_proof_with_context`]
+//! checks all three.
+
+use crate::proving::groth16::{
+    r1cs_to_qap::R1CSToQAP, CommitCarryingProof, CommitCarryingProvingKey, ContextBoundProof,
+    Groth16, PreparedVerifyingKey, ProvingKey,
+};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_relations::gr1cs::{
+    ConstraintSynthesizer, ConstraintSystemRef, Result as R1CSResult, SynthesisError,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{rand::Rng, vec::Vec};
+use sha2::{Digest, Sha256};
+
+const OTS_KEY_DOMAIN: &[u8] = b"zkbrownian/groth16-ots-key";
+const OTS_SIGNATURE_DOMAIN: &[u8] = b"zkbrownian/groth16-ots-signature";
+
+/// A circuit with the binding of a one-time key appended to its public
+/// inputs.
+#[derive(Clone, Debug)]
+pub struct ContextBoundCircuit<F: Field, C> {
+    /// The underlying circuit.
+    pub circuit: C,
+    /// The binding `H(ots_vk)`, `None` during setup.
+    pub binding: Option<F>,
+}
+
+impl<F: Field, C: ConstraintSynthesizer<F>> ConstraintSynthesizer<F> for ContextBoundCircuit<F, C> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> R1CSResult<()> {
+        self.circuit.generate_constraints(cs.clone())?;
+        cs.new_input_variable(|| self.binding.ok_or(SynthesisError::AssignmentMissing))?;
+        Ok(())
+    }
+}
+
+/// Hash the domain and `bytes` to a scalar.
+fn hash_to_field<F: PrimeField>(domain: &[u8], bytes: &[u8]) -> F {
+    let mut hasher = Sha256::new();
+    hasher.update(domain);
+    hasher.update(bytes);
+    F::from_le_bytes_mod_order(&hasher.finalize())
+}
+
+/// The public input `H(ots_vk)` that binds the proof to a one-time key.
+pub fn ots_binding<E: Pairing>(ots_vk: &E::G1Affine) -> E::ScalarField {
+    let mut bytes = Vec::new();
+    ots_vk.serialize_compressed(&mut bytes).unwrap();
+    hash_to_field(OTS_KEY_DOMAIN, &bytes)
+}
+
+/// The Schnorr challenge for the signature on `(ots_vk, proof, context)`.
+fn signature_challenge<E: Pairing, P: CanonicalSerialize>(
+    ots_vk: &E::G1Affine,
+    signature_r: &E::G1Affine,
+    proof: &P,
+    context: &[u8],
+) -> E::ScalarField {
+    let mut bytes = Vec::new();
+    ots_vk.serialize_compressed(&mut bytes).unwrap();
+    signature_r.serialize_compressed(&mut bytes).unwrap();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes.extend_from_slice(&(context.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(context);
+    hash_to_field(OTS_SIGNATURE_DOMAIN, &bytes)
+}
+
+/// Sign `proof` and `context` with the one-time key `sk`.
+///
+/// The result only verifies if `proof` was created for the binding of
+/// `sk * G`.
+pub(super) fn sign_proof<E: Pairing, P: CanonicalSerialize + CanonicalDeserialize>(
+    sk: E::ScalarField,
+    proof: P,
+    context: &[u8],
+    rng: &mut impl Rng,
+) -> ContextBoundProof<E, P> {
+    let ots_vk = (E::G1Affine::generator() * sk).into_affine();
+    let k = E::ScalarField::rand(rng);
+    let signature_r = (E::G1Affine::generator() * k).into_affine();
+    let e = signature_challenge::<E, _>(&ots_vk, &signature_r, &proof, context);
+
+    ContextBoundProof {
+        proof,
+        ots_vk,
+        signature_r,
+        signature_s: k + e * sk,
+    }
+}
+
+/// Check the one-time signature of `proof` on `context` and return the
+/// public inputs with the binding of its key appended.
+fn signed_inputs<E: Pairing, P: CanonicalSerialize + CanonicalDeserialize>(
+    proof: &ContextBoundProof<E, P>,
+    public_inputs: &[E::ScalarField],
+    context: &[u8],
+) -> Option<Vec<E::ScalarField>> {
+    let e = signature_challenge::<E, _>(&proof.ots_vk, &proof.signature_r, &proof.proof, context);
+    if E::G1Affine::generator() * proof.signature_s
+        != proof.signature_r.into_group() + proof.ots_vk * e
+    {
+        return None;
+    }
+
+    let mut inputs = public_inputs.to_vec();
+    inputs.push(ots_binding::<E>(&proof.ots_vk));
+    Some(inputs)
+}
+
+impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
+    /// Generates a random common reference string for the context-bound
+    /// mode, i.e. for the circuit with the binding of a one-time key
+    /// appended to its public inputs.
+    #[inline]
+    pub fn generate_random_parameters_with_context<C>(
+        circuit: C,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_random_parameters_with_reduction(
+            ContextBoundCircuit {
+                circuit,
+                binding: None,
+            },
+            rng,
+        )
+    }
+
+    /// Create a context-bound proof, sampling the one-time key and the
+    /// proof randomness via `rng`.
+    pub fn create_random_proof_with_context<C>(
+        circuit: C,
+        pk: &ProvingKey<E>,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<ContextBoundProof<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let sk = E::ScalarField::rand(rng);
+        let ots_vk = (E::G1Affine::generator() * sk).into_affine();
+        let proof = Self::create_random_proof_with_reduction(
+            ContextBoundCircuit {
+                circuit,
+                binding: Some(ots_binding::<E>(&ots_vk)),
+            },
+            pk,
+            rng,
+        )?;
+
+        Ok(sign_proof(sk, proof, context, rng))
+    }
+
+    /// Verify a context-bound proof `proof` against the prepared
+    /// verification key `pvk`, with respect to the public inputs of the
+    /// underlying circuit and the message `context`.
+    pub fn verify_proof_with_context(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &ContextBoundProof<E>,
+        public_inputs: &[E::ScalarField],
+        context: &[u8],
+    ) -> R1CSResult<bool> {
+        // The binding has to land on the last input of the key
+        if public_inputs.len() + 2 != pvk.vk.gamma_abc_g1.len() {
+            return Ok(false);
+        }
+
+        match signed_inputs(proof, public_inputs, context) {
+            Some(inputs) => Self::verify_proof(pvk, &proof.proof, &inputs),
+            None => Ok(false),
+        }
+    }
+
+    /// Generates a random common reference string for the commit-carrying,
+    /// context-bound mode. The binding is appended after the inputs of
+    /// `circuit`, its committed witnesses are unchanged.
+    #[inline]
+    pub fn generate_random_parameters_with_commitment_and_context<C>(
+        circuit: C,
+        commit_witness_count: usize,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<CommitCarryingProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        Self::generate_random_parameters_with_commitment(
+            ContextBoundCircuit {
+                circuit,
+                binding: None,
+            },
+            commit_witness_count,
+            rng,
+        )
+    }
+
+    /// Create a commit-carrying, context-bound proof. Also returns the
+    /// randomness `v` of `D`, needed to link it.
+    pub fn create_random_proof_with_commitment_and_context<C>(
+        circuit: C,
+        pk: &CommitCarryingProvingKey<E>,
+        context: &[u8],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<(ContextBoundProof<E, CommitCarryingProof<E>>, E::ScalarField)>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let sk = E::ScalarField::rand(rng);
+        let ots_vk = (E::G1Affine::generator() * sk).into_affine();
+        let (proof, v) = Self::create_random_proof_with_commitment(
+            ContextBoundCircuit {
+                circuit,
+                binding: Some(ots_binding::<E>(&ots_vk)),
+            },
+            pk,
+            rng,
+        )?;
+
+        Ok((sign_proof(sk, proof, context, rng), v))
+    }
+
+    /// Verify the signature and the pairing check of a commit-carrying,
+    /// context-bound proof against the prepared `vk.vk` of a commit-carrying
+    /// key.
+    ///
+    /// Like [`Groth16::verify_pairing_unlinked`], this does not bind the
+    /// public inputs, the binding included, without the link of `D`.
+    pub(crate) fn verify_pairing_unlinked_with_context(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &ContextBoundProof<E, CommitCarryingProof<E>>,
+        public_inputs: &[E::ScalarField],
+        context: &[u8],
+    ) -> R1CSResult<bool> {
+        if public_inputs.len() + 2 != pvk.vk.gamma_abc_g1.len() {
+            return Ok(false);
+        }
+
+        match signed_inputs(proof, public_inputs, context) {
+            Some(inputs) => Self::verify_pairing_unlinked(pvk, &proof.proof, &inputs),
+            None => Ok(false),
+        }
+    }
+}
@@ -141,3 +141,59 @@ pub struct ProvingKey<E: Pairing> {
     /// The elements `l_i * G` in `E::G1`.
     pub l_query: Vec<E::G1Affine>,
 }
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A proof in the commit-carrying (LegoGroth16) variant of Groth16.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitCarryingProof<E: Pairing> {
+    /// The underlying `(A, B, C)`.
+    pub proof: Proof<E>,
+    /// The commitment `D = sum_j w_j * L_j + v * eta/gamma * G` in `E::G1` to
+    /// the committed witnesses `w_j`.
+    pub d: E::G1Affine,
+}
+
+/// A verification key in the commit-carrying variant of Groth16.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitCarryingVerifyingKey<E: Pairing> {
+    /// The underlying verification key.
+    pub vk: VerifyingKey<E>,
+    /// The elements `L_j = gamma^{-1} * (beta * a_j + alpha * b_j + c_j) * G`
+    /// of the committed witnesses, in `E::G1`.
+    pub commitment_bases: Vec<E::G1Affine>,
+    /// The element `eta * gamma^{-1} * G`, the blinding base of `D`.
+    pub eta_gamma_inv_g1: E::G1Affine,
+    /// The element `eta * delta^{-1} * G`, which offsets the blinding of `D`
+    /// in `C`. Public so that proofs can be rerandomized without the
+    /// proving key.
+    pub eta_delta_inv_g1: E::G1Affine,
+}
+
+/// The prover key for the commit-carrying variant of Groth16.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitCarryingProvingKey<E: Pairing> {
+    /// The underlying proving key. The `l_query` elements of the committed
+    /// witnesses are zero, their contribution is in `D` instead.
+    pub pk: ProvingKey<E>,
+    /// The commit-carrying verification key.
+    pub vk: CommitCarryingVerifyingKey<E>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A proof in the context-bound, simulation-extractable mode of Groth16.
+///
+/// `P` is the underlying proof, a [`Proof`] or a [`CommitCarryingProof`].
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ContextBoundProof<E: Pairing, P: CanonicalSerialize + CanonicalDeserialize = Proof<E>> {
+    /// The underlying proof, whose last public input binds `ots_vk`. The
+    /// signature covers all of it, including `D` if any.
+    pub proof: P,
+    /// The one-time verification key `sk * G` in `E::G1`.
+    pub ots_vk: E::G1Affine,
+    /// The nonce commitment `R = k * G` of the one-time signature.
+    pub signature_r: E::G1Affine,
+    /// The response `s = k + e * sk` of the one-time signature.
+    pub signature_s: E::ScalarField,
+}
@@ -1,5 +1,8 @@
-use crate::proving::groth16::{r1cs_to_qap::R1CSToQAP, Groth16, ProvingKey, Vec, VerifyingKey};
-use ark_ec::{pairing::Pairing, scalar_mul::BatchMulPreprocessing, CurveGroup};
+use crate::proving::groth16::{
+    r1cs_to_qap::R1CSToQAP, CommitCarryingProvingKey, CommitCarryingVerifyingKey, Groth16,
+    ProvingKey, Vec, VerifyingKey,
+};
+use ark_ec::{pairing::Pairing, scalar_mul::BatchMulPreprocessing, AffineRepr, CurveGroup};
 use ark_ff::{Field, UniformRand, Zero};
 use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
 use ark_relations::gr1cs::{
@@ -42,6 +45,70 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         )
     }
 
+    /// Generates a random common reference string for the commit-carrying
+    /// variant, in which proofs carry a commitment `D` to the first
+    /// `commit_witness_count` witness variables of the circuit.
+    ///
+    /// Fails with [`SynthesisError::MalformedVerifyingKey`] if the circuit has
+    /// fewer witness variables, as the key would commit to variables that do
+    /// not exist. [`SynthesisError`] is defined upstream and has no variant
+    /// for an out-of-range argument; this is the one closest to the failure,
+    /// a verifying key that cannot be built.
+    pub fn generate_random_parameters_with_commitment<C>(
+        circuit: C,
+        commit_witness_count: usize,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<CommitCarryingProvingKey<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let alpha = E::ScalarField::rand(rng);
+        let beta = E::ScalarField::rand(rng);
+        let gamma = E::ScalarField::rand(rng);
+        let delta = E::ScalarField::rand(rng);
+        let eta = E::ScalarField::rand(rng);
+
+        let g1_generator = E::G1::rand(rng);
+        let g2_generator = E::G2::rand(rng);
+
+        let mut pk = Self::generate_parameters_with_qap(
+            circuit,
+            alpha,
+            beta,
+            gamma,
+            delta,
+            g1_generator,
+            g2_generator,
+            rng,
+        )?;
+        if commit_witness_count > pk.l_query.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+
+        // Move the committed witnesses from the delta side of the pairing
+        // check to the gamma side: L_j = (delta / gamma) * l_j
+        let gamma_inverse = gamma.inverse().unwrap();
+        let delta_inverse = delta.inverse().unwrap();
+        let delta_gamma_inverse = delta * gamma_inverse;
+        let commitment_bases = E::G1::normalize_batch(
+            &cfg_iter!(pk.l_query[..commit_witness_count])
+                .map(|l| *l * delta_gamma_inverse)
+                .collect::<Vec<_>>(),
+        );
+        for l in &mut pk.l_query[..commit_witness_count] {
+            *l = E::G1Affine::zero();
+        }
+
+        let vk = CommitCarryingVerifyingKey {
+            vk: pk.vk.clone(),
+            commitment_bases,
+            eta_gamma_inv_g1: (g1_generator * (eta * gamma_inverse)).into_affine(),
+            eta_delta_inv_g1: (g1_generator * (eta * delta_inverse)).into_affine(),
+        };
+
+        Ok(CommitCarryingProvingKey { pk, vk })
+    }
+
     /// Create parameters for a circuit, given some toxic waste, R1CS to QAP
     /// calculator and group generators
     pub fn generate_parameters_with_qap<C>(
@@ -0,0 +1,192 @@
+//! Controlled malleability of Groth16 statements and proofs.
+//!
+//! As in `controlled-malleability.sage`, a transformation is a pair
+//! `(T_x, T_w)` acting on a statement and its witness, `x' = T_x * (x || 1)`
+//! and `w' = T_w * (w || 1)`. It is witness-consistent if `w'` is a witness
+//! for `x'` whenever `w` is one for `x`, so it never turns a true statement
+//! into a false one. This module implements the witness-consistent
+//! transformations that map a Groth16 proof for `x` to one for `x'` without
+//! the witness.
+//!
+//! Here the statement is the public input vector, together with `D` in the
+//! commit-carrying variant. The witness is the full assignment `z`, together
+//! with the opening `v` of `D` and the prover's randomness. Every proof element
+//! is linear in the QAP columns `beta * u_i + alpha * v_i + w_i` of `z`. A
+//! translation `z' = z + dz` therefore leaves the proof valid iff `dz` lies
+//! in the kernel of the constraint matrices, which is exactly when `z'`
+//! satisfies the constraints whenever `z` does. [`LibsnarkReduction`] gives
+//! every input a row of its own, so `dz` cannot touch the inputs. The family
+//! is:
+//!
+//! - [`Transformation::Rerandomize`]: `T_x` and `T_w` are the identity on
+//!   `(x, z)`; `(A, B, C)` are refreshed;
+//! - [`Transformation::Reblind`]: `T_x` moves `D` by `t * eta/gamma * G` and
+//!   `T_w` moves `v` by `t`; commit-carrying proofs only;
+//! - [`Transformation::Translate`]: `T_x` is the identity and `T_w` moves the
+//!   witness along the kernel; the proof stays as it is.
+//!
+//! Compositions of these are also in the family. Shifting or scaling a public
+//! input is not: the commit-carrying check absorbs the change in `D`, but
+//! the shifted statement need not have a witness.
+//!
+//! [`LibsnarkReduction`]: crate::proving::groth16::r1cs_to_qap::LibsnarkReduction
+
+use crate::proving::groth16::{
+    r1cs_to_qap::R1CSToQAP, CommitCarryingProof, CommitCarryingVerifyingKey, Groth16, Proof,
+    VerifyingKey,
+};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{Field, Zero};
+use ark_relations::gr1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult, SynthesisMode,
+    R1CS_PREDICATE_LABEL,
+};
+use ark_std::vec::Vec;
+
+/// A witness-consistent transformation of a Groth16 statement and its proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Transformation<F: Field> {
+    /// Identity on the statement; `(A, B, C)` become
+    /// `(A / r1, r1 * B + r1 * r2 * delta, C + r2 * A)`. `r1` must be
+    /// nonzero.
+    Rerandomize {
+        /// The factor `r1`.
+        r1: F,
+        /// The factor `r2`.
+        r2: F,
+    },
+    /// Reblind the commitment: `D' = D + t * eta/gamma * G`, opened with
+    /// `v + t`.
+    Reblind(F),
+    /// Move the witness variables by the given vector, which must lie in the
+    /// kernel of the constraint matrices.
+    Translate(Vec<F>),
+}
+
+/// What a transformation does to the link of `D` to a Pedersen commitment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkEffect {
+    /// `D` is unchanged and the link still verifies.
+    Kept,
+    /// `D` is reblinded; the holder of its opening can link it again.
+    Relinkable,
+}
+
+impl<F: Field> Transformation<F> {
+    /// The witness `T_w` maps `witness` to, or `None` if a translation does
+    /// not match its length.
+    pub fn apply_to_witness(&self, witness: &[F]) -> Option<Vec<F>> {
+        match self {
+            Self::Translate(shift) if shift.len() != witness.len() => None,
+            Self::Translate(shift) => {
+                Some(witness.iter().zip(shift).map(|(w, s)| *w + s).collect())
+            }
+            Self::Rerandomize { .. } | Self::Reblind(_) => Some(witness.to_vec()),
+        }
+    }
+
+    /// What the transformation does to the link of `D`.
+    pub fn link_effect(&self) -> LinkEffect {
+        match self {
+            Self::Reblind(t) if !t.is_zero() => LinkEffect::Relinkable,
+            _ => LinkEffect::Kept,
+        }
+    }
+}
+
+impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
+    /// Whether `transformation` is witness-consistent for `circuit`, with the
+    /// first `commit_witness_count` witness variables committed in `D`.
+    ///
+    /// A translation must lie in the kernel of the constraint matrices. When
+    /// it moves committed variables, their part alone must also lie there, so
+    /// that `D` and `C` each keep their value.
+    pub fn is_admissible<C>(
+        circuit: C,
+        commit_witness_count: usize,
+        transformation: &Transformation<E::ScalarField>,
+    ) -> R1CSResult<bool>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let shift = match transformation {
+            Transformation::Rerandomize { r1, .. } => return Ok(!r1.is_zero()),
+            Transformation::Reblind(_) => return Ok(commit_witness_count > 0),
+            Transformation::Translate(shift) => shift,
+        };
+
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        if shift.len() != cs.num_witness_variables() || commit_witness_count > shift.len() {
+            return Ok(false);
+        }
+
+        let num_instance_variables = cs.num_instance_variables();
+        let matrices = &cs.to_matrices().unwrap()[R1CS_PREDICATE_LABEL];
+        let in_kernel = |shift: &[E::ScalarField]| {
+            matrices.iter().flatten().all(|row| {
+                row.iter()
+                    .filter_map(|(coeff, index)| {
+                        let j = index.checked_sub(num_instance_variables)?;
+                        Some(*coeff * shift.get(j).copied().unwrap_or_default())
+                    })
+                    .sum::<E::ScalarField>()
+                    .is_zero()
+            })
+        };
+
+        Ok(in_kernel(shift) && in_kernel(&shift[..commit_witness_count]))
+    }
+
+    /// Map a proof under `vk` to one for the transformed statement, without
+    /// the witness.
+    ///
+    /// Returns `None` for a zero `r1` or for [`Transformation::Reblind`],
+    /// which needs a commit-carrying key. Whether a translation is
+    /// witness-consistent is checked by [`Groth16::is_admissible`].
+    pub fn transform_proof(
+        vk: &VerifyingKey<E>,
+        proof: &Proof<E>,
+        transformation: &Transformation<E::ScalarField>,
+    ) -> Option<Proof<E>> {
+        match transformation {
+            Transformation::Rerandomize { r1, r2 } => {
+                let new_a = proof.a * r1.inverse()?;
+                let new_b = proof.b * *r1 + vk.delta_g2 * (*r1 * r2);
+                let new_c = proof.c.into_group() + proof.a * *r2;
+                Some(Proof {
+                    a: new_a.into_affine(),
+                    b: new_b.into_affine(),
+                    c: new_c.into_affine(),
+                })
+            }
+            Transformation::Reblind(_) => None,
+            Transformation::Translate(_) => Some(proof.clone()),
+        }
+    }
+
+    /// Map a commit-carrying proof under `vk` to one for the transformed
+    /// statement, without the witness.
+    ///
+    /// Returns `None` for a zero `r1`.
+    pub fn transform_proof_with_commitment(
+        vk: &CommitCarryingVerifyingKey<E>,
+        proof: &CommitCarryingProof<E>,
+        transformation: &Transformation<E::ScalarField>,
+    ) -> Option<CommitCarryingProof<E>> {
+        let Transformation::Reblind(t) = transformation else {
+            return Some(CommitCarryingProof {
+                proof: Self::transform_proof(&vk.vk, &proof.proof, transformation)?,
+                d: proof.d,
+            });
+        };
+
+        let mut reblinded = proof.clone();
+        reblinded.d = (proof.d.into_group() + vk.eta_gamma_inv_g1 * *t).into_affine();
+        reblinded.proof.c = (proof.proof.c.into_group() - vk.eta_delta_inv_g1 * *t).into_affine();
+        Some(reblinded)
+    }
+}
@@ -1,6 +1,10 @@
 //! An implementation of the [`Groth16`] zkSNARK.
 //!
 //! [`Groth16`]: https://eprint.iacr.org/2016/260.pdf
+//!
+//! Besides plain Groth16 it supports a commit-carrying variant in the style
+//! of LegoGroth16 ([CFQ19](https://eprint.iacr.org/2019/142)), whose proofs
+//! carry a commitment `D` to the first witness variables of the circuit.
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(unused, nonstandard_style, rust_2018_idioms, missing_docs)]
 #![allow(clippy::many_single_char_names, clippy::op_ref)]
@@ -21,6 +25,12 @@ pub mod prover;
 /// Verify proofs for the Groth16 zkSNARK construction.
 pub mod verifier;
 
+/// Transform statements and proofs without the witness.
+pub mod malleability;
+
+/// Bind proofs to a message context with a one-time signature.
+pub mod context;
+
 /// Constraints for the Groth16 verifier.
 #[cfg(feature = "r1cs")]
 pub mod constraints;
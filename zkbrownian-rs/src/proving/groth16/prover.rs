@@ -1,11 +1,14 @@
-use crate::proving::groth16::{r1cs_to_qap::R1CSToQAP, Groth16, Proof, ProvingKey, VerifyingKey};
+use crate::proving::groth16::{
+    r1cs_to_qap::R1CSToQAP, CommitCarryingProof, CommitCarryingProvingKey,
+    CommitCarryingVerifyingKey, Groth16, Proof, ProvingKey, VerifyingKey,
+};
 use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_ff::{Field, PrimeField, UniformRand, Zero};
 use ark_poly::GeneralEvaluationDomain;
 use ark_relations::{
     gr1cs::{
         ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
-        SynthesisMode,
+        SynthesisError, SynthesisMode,
     },
     utils::matrix::Matrix,
 };
@@ -185,6 +188,28 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         QAP: R1CSToQAP,
     {
         let prover_time = start_timer!(|| "Groth16::Prover");
+        let (h, input_assignment, aux_assignment) = Self::synthesize_assignment(circuit)?;
+        let proof =
+            Self::create_proof_with_assignment(pk, r, s, &h, &input_assignment, &aux_assignment)?;
+        end_timer!(prover_time);
+
+        Ok(proof)
+    }
+
+    /// Synthesize `circuit` and compute the QAP witness map. Returns `h`, the
+    /// instance assignment without the leading one, and the witness
+    /// assignment.
+    #[allow(clippy::type_complexity)]
+    fn synthesize_assignment<C>(
+        circuit: C,
+    ) -> R1CSResult<(
+        Vec<E::ScalarField>,
+        Vec<E::ScalarField>,
+        Vec<E::ScalarField>,
+    )>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
         let cs = ConstraintSystem::new_ref();
 
         // Set the optimization goal
@@ -210,18 +235,73 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         end_timer!(witness_map_time);
 
         let prover = cs.borrow().unwrap();
-        let proof = Self::create_proof_with_assignment(
-            pk,
+        Ok((
+            h,
+            prover.instance_assignment().unwrap()[1..].to_vec(),
+            prover.witness_assignment().unwrap().to_vec(),
+        ))
+    }
+
+    /// Create a commit-carrying proof that is zero-knowledge, sampling `r`,
+    /// `s` and the commitment randomness `v` via `rng`. Returns the proof and
+    /// `v`, which together with the committed witnesses opens `D`.
+    #[inline]
+    pub fn create_random_proof_with_commitment<C>(
+        circuit: C,
+        pk: &CommitCarryingProvingKey<E>,
+        rng: &mut impl Rng,
+    ) -> R1CSResult<(CommitCarryingProof<E>, E::ScalarField)>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let r = E::ScalarField::rand(rng);
+        let s = E::ScalarField::rand(rng);
+        let v = E::ScalarField::rand(rng);
+
+        let proof = Self::create_proof_with_commitment(circuit, pk, r, s, v)?;
+        Ok((proof, v))
+    }
+
+    /// Create a commit-carrying proof using randomness `r`, `s` and
+    /// commitment randomness `v`.
+    ///
+    /// With `l_j` removed from the proving key for the committed witnesses,
+    /// `C` lacks their contribution; `D` carries it on the gamma side
+    /// instead, and `v * eta` is added to `D` and taken out of `C`.
+    pub fn create_proof_with_commitment<C>(
+        circuit: C,
+        pk: &CommitCarryingProvingKey<E>,
+        r: E::ScalarField,
+        s: E::ScalarField,
+        v: E::ScalarField,
+    ) -> R1CSResult<CommitCarryingProof<E>>
+    where
+        C: ConstraintSynthesizer<E::ScalarField>,
+    {
+        let prover_time = start_timer!(|| "Groth16::Prover with commitment");
+        let (h, input_assignment, aux_assignment) = Self::synthesize_assignment(circuit)?;
+        let committed = aux_assignment
+            .get(..pk.vk.commitment_bases.len())
+            .ok_or(SynthesisError::AssignmentMissing)?;
+
+        let mut proof = Self::create_proof_with_assignment(
+            &pk.pk,
             r,
             s,
             &h,
-            &prover.instance_assignment().unwrap()[1..],
-            &prover.witness_assignment().unwrap(),
+            &input_assignment,
+            &aux_assignment,
         )?;
+        proof.c = (proof.c.into_group() - pk.vk.eta_delta_inv_g1 * v).into_affine();
 
+        let d =
+            E::G1::msm_unchecked(&pk.vk.commitment_bases, committed) + pk.vk.eta_gamma_inv_g1 * v;
         end_timer!(prover_time);
 
-        Ok(proof)
+        Ok(CommitCarryingProof {
+            proof,
+            d: d.into_affine(),
+        })
     }
 
     /// Given a Groth16 proof, returns a fresh proof of the same statement. For
@@ -258,6 +338,38 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         }
     }
 
+    /// Given a commit-carrying proof, returns a fresh proof of the same
+    /// statement together with a fresh commitment `D'` to the same values,
+    /// as in SAVER. `(A, B, C)` are rerandomized as in
+    /// [`Self::rerandomize_proof`], and `D' = D + v' * eta/gamma * G` with
+    /// `C` compensated by `- v' * eta/delta * G`. The output is distributed
+    /// as a fresh honest proof, independently of the input proof.
+    ///
+    /// Also returns `v'`: a holder of the opening `v` of `D` opens `D'` with
+    /// `v + v'`. A link of `D` to a Pedersen commitment does not carry over
+    /// to `D'`, and nobody without the openings can prove a new one;
+    /// [`crate::proving::commitment_link::rerandomize_linked_proof`] does
+    /// both for the holder.
+    pub fn rerandomize_proof_with_commitment(
+        vk: &CommitCarryingVerifyingKey<E>,
+        proof: &CommitCarryingProof<E>,
+        rng: &mut impl Rng,
+    ) -> (CommitCarryingProof<E>, E::ScalarField) {
+        let mut new_proof = Self::rerandomize_proof(&vk.vk, &proof.proof, rng);
+        let v = E::ScalarField::rand(rng);
+
+        new_proof.c = (new_proof.c.into_group() - vk.eta_delta_inv_g1 * v).into_affine();
+        let new_d = proof.d.into_group() + vk.eta_gamma_inv_g1 * v;
+
+        (
+            CommitCarryingProof {
+                proof: new_proof,
+                d: new_d.into_affine(),
+            },
+            v,
+        )
+    }
+
     fn calculate_coeff<G: AffineRepr>(
         initial: G::Group,
         query: &[G],
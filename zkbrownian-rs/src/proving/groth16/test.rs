@@ -1,9 +1,22 @@
-use crate::proving::groth16::{prepare_verifying_key, Groth16};
+use crate::crypto::generators::protocol_generators;
+use crate::crypto::pedersen::G1PedersenParams;
+use crate::proving::commitment_link::{
+    create_linked_proof_with_context, verify_linked_proof_with_context, LinkedContextProof,
+};
+use crate::proving::groth16::{
+    context,
+    malleability::{LinkEffect, Transformation},
+    prepare_verifying_key, CommitCarryingProof, ContextBoundProof, Groth16,
+};
+use crate::proving::transcript::Sha256Transcript;
+use crate::types::{PairingEngine, ScalarField};
 use ark_crypto_primitives::snark::{CircuitSpecificSetupSNARK, SNARK};
-use ark_ec::pairing::Pairing;
-use ark_ff::Field;
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::{Field, Zero};
 use ark_relations::{
-    gr1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+    gr1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, Variable,
+    },
     lc,
 };
 use ark_std::{
@@ -42,6 +55,28 @@ impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for MySillyCircuit<C
     }
 }
 
+/// `(a + b) * 1 = c`, whose witnesses can move along `(1, -1)`
+struct SumCircuit<F: Field> {
+    a: Option<F>,
+    b: Option<F>,
+    c: Option<F>,
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for SumCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let a = cs.new_witness_variable(|| self.a.ok_or(SynthesisError::AssignmentMissing))?;
+        let b = cs.new_witness_variable(|| self.b.ok_or(SynthesisError::AssignmentMissing))?;
+        let c = cs.new_input_variable(|| self.c.ok_or(SynthesisError::AssignmentMissing))?;
+
+        cs.enforce_r1cs_constraint(|| lc!() + a + b, || lc!() + Variable::One, || lc!() + c)?;
+
+        Ok(())
+    }
+}
+
 fn test_prove_and_verify<E>(n_iters: usize)
 where
     E: Pairing,
@@ -117,8 +152,517 @@ where
     }
 }
 
+fn test_prove_and_verify_with_commitment<E>(n_iters: usize)
+where
+    E: Pairing,
+{
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    // Commit to both witnesses a and b
+    let pk = Groth16::<E>::generate_random_parameters_with_commitment(
+        MySillyCircuit { a: None, b: None },
+        2,
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key::<E>(&pk.vk.vk);
+    assert_eq!(pk.vk.commitment_bases.len(), 2);
+
+    for _ in 0..n_iters {
+        let a = E::ScalarField::rand(&mut rng);
+        let b = E::ScalarField::rand(&mut rng);
+        let mut c = a;
+        c *= b;
+
+        let (proof, v) = Groth16::<E>::create_random_proof_with_commitment(
+            MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &pk,
+            &mut rng,
+        )
+        .unwrap();
+
+        assert!(Groth16::<E>::verify_pairing_unlinked(&pvk, &proof, &[c]).unwrap());
+        assert!(!Groth16::<E>::verify_pairing_unlinked(&pvk, &proof, &[a]).unwrap());
+
+        // D opens to (a, b) with randomness v
+        let bases = &pk.vk.commitment_bases;
+        let d = bases[0] * a + bases[1] * b + pk.vk.eta_gamma_inv_g1 * v;
+        assert_eq!(proof.d, d.into_affine());
+
+        // D cannot be dropped, and shifting it alone breaks the check
+        assert!(!Groth16::<E>::verify_proof(&pvk, &proof.proof, &[c]).unwrap());
+        let mut shifted = proof.clone();
+        shifted.d = (proof.d.into_group() + bases[0]).into_affine();
+        assert!(!Groth16::<E>::verify_pairing_unlinked(&pvk, &shifted, &[c]).unwrap());
+
+        // But the check alone does not bind the inputs: D absorbs a shift of
+        // the input by a, only the link of D to a Pedersen commitment
+        // rules this out
+        let mut forged = proof.clone();
+        forged.d = (proof.d.into_group() - pvk.vk.gamma_abc_g1[1] * a).into_affine();
+        assert!(Groth16::<E>::verify_pairing_unlinked(&pvk, &forged, &[c + a]).unwrap());
+    }
+
+    // More committed witnesses than the circuit has
+    assert_eq!(
+        Groth16::<E>::generate_random_parameters_with_commitment(
+            MySillyCircuit::<E::ScalarField> { a: None, b: None },
+            3,
+            &mut rng,
+        )
+        .err(),
+        Some(SynthesisError::MalformedVerifyingKey)
+    );
+}
+
+fn test_rerandomize_with_commitment<E>()
+where
+    E: Pairing,
+{
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(test_rng().next_u64());
+
+    let pk = Groth16::<E>::generate_random_parameters_with_commitment(
+        MySillyCircuit { a: None, b: None },
+        2,
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key::<E>(&pk.vk.vk);
+
+    let a = E::ScalarField::rand(&mut rng);
+    let b = E::ScalarField::rand(&mut rng);
+    let mut c = a;
+    c *= b;
+    let prove = |r, s, v| {
+        Groth16::<E>::create_proof_with_commitment(
+            MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &pk,
+            r,
+            s,
+            v,
+        )
+        .unwrap()
+    };
+    let (r, s, v1) = (
+        E::ScalarField::rand(&mut rng),
+        E::ScalarField::rand(&mut rng),
+        E::ScalarField::rand(&mut rng),
+    );
+    let proof1 = prove(r, s, v1);
+
+    // The fresh pair verifies, and D' opens to (a, b) with v + v'.
+    // rerandomize_proof_with_commitment samples r1, r2 and then v'
+    let mut factors = rng.clone();
+    let (proof3, shift) =
+        Groth16::<E>::rerandomize_proof_with_commitment(&pk.vk, &proof1, &mut rng);
+    assert!(Groth16::<E>::verify_pairing_unlinked(&pvk, &proof3, &[c]).unwrap());
+    assert!(!Groth16::<E>::verify_pairing_unlinked(&pvk, &proof3, &[a]).unwrap());
+    let bases = &pk.vk.commitment_bases;
+    let d = bases[0] * a + bases[1] * b + pk.vk.eta_gamma_inv_g1 * (v1 + shift);
+    assert_eq!(proof3.d, d.into_affine());
+
+    // The output is exactly the fresh proof with s + r2 and v + v', with A
+    // and B scaled by r1. It depends on the input only through r, and
+    // A' = A / r1 is uniform for a uniform r1 whatever r is.
+    let r1 = E::ScalarField::rand(&mut factors);
+    let r2 = E::ScalarField::rand(&mut factors);
+    assert_eq!(shift, E::ScalarField::rand(&mut factors));
+    let fresh = prove(r, s + r2, v1 + shift);
+    let scaled = Groth16::<E>::transform_proof_with_commitment(
+        &pk.vk,
+        &fresh,
+        &Transformation::Rerandomize {
+            r1,
+            r2: E::ScalarField::zero(),
+        },
+    )
+    .unwrap();
+    assert_eq!(proof3, scaled);
+    assert!(proof3 != proof1);
+}
+
+fn test_malleability<E>()
+where
+    E: Pairing,
+{
+    let mut rng = test_rng();
+    let blank = || SumCircuit::<E::ScalarField> {
+        a: None,
+        b: None,
+        c: None,
+    };
+    let circuit = |a, b, c| SumCircuit {
+        a: Some(a),
+        b: Some(b),
+        c: Some(c),
+    };
+    // Whether (a, b) is a witness for c
+    let satisfied = |witness: &[E::ScalarField], c| {
+        let cs = ConstraintSystem::new_ref();
+        circuit(witness[0], witness[1], c)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    };
+
+    let pk =
+        Groth16::<E>::generate_random_parameters_with_commitment(blank(), 2, &mut rng).unwrap();
+    let pvk = prepare_verifying_key::<E>(&pk.vk.vk);
+    let verify = |proof: &CommitCarryingProof<E>, inputs: &[E::ScalarField]| {
+        Groth16::<E>::verify_pairing_unlinked(&pvk, proof, inputs).unwrap()
+    };
+
+    let a = E::ScalarField::rand(&mut rng);
+    let b = E::ScalarField::rand(&mut rng);
+    let c = a + b;
+    let (proof, v) =
+        Groth16::<E>::create_random_proof_with_commitment(circuit(a, b, c), &pk, &mut rng).unwrap();
+
+    let r1 = E::ScalarField::rand(&mut rng);
+    let r2 = E::ScalarField::rand(&mut rng);
+    let t = E::ScalarField::rand(&mut rng);
+    let delta = E::ScalarField::rand(&mut rng);
+
+    // The family: each transformation keeps the statement true under the
+    // transformed witness, and the transformed proof verifies for it
+    let family = [
+        Transformation::Rerandomize { r1, r2 },
+        Transformation::Reblind(t),
+        Transformation::Translate(vec![delta, -delta]),
+    ];
+    for transformation in &family {
+        assert!(Groth16::<E>::is_admissible(blank(), 2, transformation).unwrap());
+        let witness = transformation.apply_to_witness(&[a, b]).unwrap();
+        assert!(satisfied(&witness, c));
+        let transformed =
+            Groth16::<E>::transform_proof_with_commitment(&pk.vk, &proof, transformation).unwrap();
+        assert!(verify(&transformed, &[c]));
+    }
+
+    // D is kept by rerandomization, moved by reblinding, and opened by the
+    // translated witness
+    let bases = &pk.vk.commitment_bases;
+    let open = |a, b, v| (bases[0] * a + bases[1] * b + pk.vk.eta_gamma_inv_g1 * v).into_affine();
+    let transformed: Vec<_> = family
+        .iter()
+        .map(|t| Groth16::<E>::transform_proof_with_commitment(&pk.vk, &proof, t).unwrap())
+        .collect();
+    assert_eq!(transformed[0].d, proof.d);
+    assert_eq!(family[0].link_effect(), LinkEffect::Kept);
+    assert_eq!(transformed[1].d, open(a, b, v + t));
+    assert_eq!(family[1].link_effect(), LinkEffect::Relinkable);
+    assert_eq!(transformed[2], proof);
+    assert_eq!(transformed[2].d, open(a + delta, b - delta, v));
+    assert_eq!(family[2].link_effect(), LinkEffect::Kept);
+
+    // The family is closed under composition
+    let composed = family
+        .iter()
+        .try_fold(proof.clone(), |proof, t| {
+            Groth16::<E>::transform_proof_with_commitment(&pk.vk, &proof, t)
+        })
+        .unwrap();
+    assert!(verify(&composed, &[c]));
+
+    // Not in the family: a zero factor, a translation off the kernel or of
+    // the wrong length, or one that moves D's part off the kernel
+    let off_kernel = Transformation::Translate(vec![delta, E::ScalarField::zero()]);
+    assert!(!satisfied(
+        &off_kernel.apply_to_witness(&[a, b]).unwrap(),
+        c
+    ));
+    let zero_factor = Transformation::Rerandomize {
+        r1: E::ScalarField::zero(),
+        r2,
+    };
+    for transformation in [
+        &zero_factor,
+        &off_kernel,
+        &Transformation::Translate(vec![delta]),
+    ] {
+        assert!(!Groth16::<E>::is_admissible(blank(), 2, transformation).unwrap());
+    }
+    assert!(Groth16::<E>::transform_proof_with_commitment(&pk.vk, &proof, &zero_factor).is_none());
+    assert!(!Groth16::<E>::is_admissible(blank(), 1, &family[2]).unwrap());
+
+    // Shifting the input with a matching witness keeps the statement true,
+    // but the input has a row of its own: the proof does not carry over and
+    // only proving again gives one
+    assert!(satisfied(&[a + delta, b], c + delta));
+    assert!(!verify(&proof, &[c + delta]));
+    let (shifted, _) = Groth16::<E>::create_random_proof_with_commitment(
+        circuit(a + delta, b, c + delta),
+        &pk,
+        &mut rng,
+    )
+    .unwrap();
+    assert!(verify(&shifted, &[c + delta]));
+
+    // Plain keys admit the family without reblinding
+    let (pk, vk) = Groth16::<E>::setup(blank(), &mut rng).unwrap();
+    let pvk = prepare_verifying_key::<E>(&vk);
+    let proof = Groth16::<E>::prove(&pk, circuit(a, b, c), &mut rng).unwrap();
+    for transformation in [&family[0], &family[2]] {
+        assert!(Groth16::<E>::is_admissible(blank(), 0, transformation).unwrap());
+        let transformed = Groth16::<E>::transform_proof(&vk, &proof, transformation).unwrap();
+        assert!(Groth16::<E>::verify_proof(&pvk, &transformed, &[c]).unwrap());
+    }
+    assert!(!Groth16::<E>::is_admissible(blank(), 0, &family[1]).unwrap());
+    assert!(Groth16::<E>::transform_proof(&vk, &proof, &family[1]).is_none());
+}
+
+fn test_prove_and_verify_with_context<E>()
+where
+    E: Pairing,
+{
+    let mut rng = test_rng();
+
+    let pk = Groth16::<E>::generate_random_parameters_with_context(
+        MySillyCircuit { a: None, b: None },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key::<E>(&pk.vk);
+
+    let a = E::ScalarField::rand(&mut rng);
+    let b = E::ScalarField::rand(&mut rng);
+    let mut c = a;
+    c *= b;
+    let proof = Groth16::<E>::create_random_proof_with_context(
+        MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        },
+        &pk,
+        b"hop 1",
+        &mut rng,
+    )
+    .unwrap();
+    let verify = |proof: &ContextBoundProof<E>, inputs: &[E::ScalarField], context: &[u8]| {
+        Groth16::<E>::verify_proof_with_context(&pvk, proof, inputs, context).unwrap()
+    };
+
+    assert!(verify(&proof, &[c], b"hop 1"));
+    assert!(!verify(&proof, &[a], b"hop 1"));
+    assert!(!verify(&proof, &[c], b"hop 2"));
+    // The binding is not an input of the caller's
+    let binding = context::ots_binding::<E>(&proof.ots_vk);
+    assert!(!verify(&proof, &[c, binding], b"hop 1"));
+
+    // A rerandomized proof is rejected in any context
+    let mut rerandomized = proof.clone();
+    rerandomized.proof = Groth16::<E>::rerandomize_proof(&pk.vk, &proof.proof, &mut rng);
+    let inputs = [c, binding];
+    assert!(Groth16::<E>::verify_proof(&pvk, &rerandomized.proof, &inputs).unwrap());
+    assert!(!verify(&rerandomized, &[c], b"hop 1"));
+    assert!(!verify(&rerandomized, &[c], b"hop 2"));
+
+    // Re-signing the proof, rerandomized or not, for another context under
+    // a fresh one-time key changes the binding
+    for stolen in [&proof.proof, &rerandomized.proof] {
+        let sk = E::ScalarField::rand(&mut rng);
+        let transplanted = context::sign_proof::<E, _>(sk, stolen.clone(), b"hop 2", &mut rng);
+        assert!(!verify(&transplanted, &[c], b"hop 2"));
+    }
+
+    // An honest prover proves again for the new context
+    let proof = Groth16::<E>::create_random_proof_with_context(
+        MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        },
+        &pk,
+        b"hop 2",
+        &mut rng,
+    )
+    .unwrap();
+    assert!(verify(&proof, &[c], b"hop 2"));
+}
+
+fn test_prove_and_verify_with_commitment_and_context() {
+    type E = PairingEngine;
+    let mut rng = test_rng();
+
+    let pk = Groth16::<E>::generate_random_parameters_with_commitment_and_context(
+        MySillyCircuit { a: None, b: None },
+        2,
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key::<E>(&pk.vk.vk);
+    let params = G1PedersenParams::from_generators(protocol_generators(), 2).unwrap();
+
+    let a = ScalarField::rand(&mut rng);
+    let b = ScalarField::rand(&mut rng);
+    let c = a * b;
+    let (commitment, opening) = params.commit_random(&[a, b], &mut rng).unwrap();
+    let linked = create_linked_proof_with_context(
+        MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        },
+        &pk,
+        &params,
+        (&commitment.point, &opening),
+        b"hop 1",
+        &mut Sha256Transcript::new(b"test"),
+        &mut rng,
+    )
+    .unwrap();
+    let verify = |proof: &LinkedContextProof, inputs: &[ScalarField], context: &[u8]| {
+        verify_linked_proof_with_context(
+            &pvk,
+            &pk.vk,
+            &params,
+            inputs,
+            &commitment.point,
+            context,
+            proof,
+            &mut Sha256Transcript::new(b"test"),
+        )
+    };
+    let with_proof = |proof: ContextBoundProof<E, CommitCarryingProof<E>>| LinkedContextProof {
+        proof,
+        link: linked.link.clone(),
+    };
+
+    assert!(verify(&linked, &[c], b"hop 1"));
+    assert!(!verify(&linked, &[a], b"hop 1"));
+    assert!(!verify(&linked, &[c], b"hop 2"));
+    // The committed witnesses are those of the circuit
+    let (swapped, _) = params.commit_random(&[b, a], &mut rng).unwrap();
+    assert!(!verify_linked_proof_with_context(
+        &pvk,
+        &pk.vk,
+        &params,
+        &[c],
+        &swapped.point,
+        b"hop 1",
+        &linked,
+        &mut Sha256Transcript::new(b"test"),
+    ));
+
+    // D is signed: reblinding it keeps the pairing check but not the
+    // signature
+    let proof = &linked.proof;
+    let binding = context::ots_binding::<E>(&proof.ots_vk);
+    let inputs = [c, binding];
+    let reblinded = Groth16::<E>::transform_proof_with_commitment(
+        &pk.vk,
+        &proof.proof,
+        &Transformation::Reblind(ScalarField::rand(&mut rng)),
+    )
+    .unwrap();
+    assert!(Groth16::<E>::verify_pairing_unlinked(&pvk, &reblinded, &inputs).unwrap());
+    let mut relayed = proof.clone();
+    relayed.proof = reblinded;
+    assert!(!verify(&with_proof(relayed.clone()), &[c], b"hop 1"));
+    let (rerandomized, _) =
+        Groth16::<E>::rerandomize_proof_with_commitment(&pk.vk, &proof.proof, &mut rng);
+    relayed.proof = rerandomized;
+    assert!(!verify(&with_proof(relayed), &[c], b"hop 1"));
+
+    // The binding itself can be shifted to that of a fresh key: the
+    // signature and the pairing check pass, but D no longer opens to the
+    // committed witnesses and the link rejects it
+    let sk = ScalarField::rand(&mut rng);
+    let ots_vk = (<E as Pairing>::G1Affine::generator() * sk).into_affine();
+    let mut shifted = proof.proof.clone();
+    let delta = context::ots_binding::<E>(&ots_vk) - binding;
+    shifted.d = (proof.proof.d.into_group() - pk.vk.vk.gamma_abc_g1[2] * delta).into_affine();
+    assert_ne!(shifted.d, proof.proof.d);
+    let transplanted = context::sign_proof::<E, _>(sk, shifted, b"hop 2", &mut rng);
+    assert!(Groth16::<E>::verify_pairing_unlinked_with_context(
+        &pvk,
+        &transplanted,
+        &[c],
+        b"hop 2"
+    )
+    .unwrap());
+    assert!(!verify(&with_proof(transplanted), &[c], b"hop 2"));
+}
+
+fn test_verify_batch<E>(n: usize)
+where
+    E: Pairing,
+{
+    let mut rng = test_rng();
+
+    let params = Groth16::<E>::generate_random_parameters_with_reduction(
+        MySillyCircuit { a: None, b: None },
+        &mut rng,
+    )
+    .unwrap();
+    let pvk = prepare_verifying_key::<E>(&params.vk);
+
+    let mut proofs = Vec::with_capacity(n);
+    let mut inputs = Vec::with_capacity(n);
+    for _ in 0..n {
+        let a = E::ScalarField::rand(&mut rng);
+        let b = E::ScalarField::rand(&mut rng);
+        let mut c = a;
+        c *= b;
+        let proof = Groth16::<E>::create_random_proof_with_reduction(
+            MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            },
+            &params,
+            &mut rng,
+        )
+        .unwrap();
+        proofs.push(proof);
+        inputs.push(vec![c]);
+    }
+    let batch = |inputs: &[Vec<E::ScalarField>], rng: &mut ark_std::rand::rngs::StdRng| {
+        let batch: Vec<_> = proofs
+            .iter()
+            .zip(inputs)
+            .map(|(proof, inputs)| (proof, inputs.as_slice()))
+            .collect();
+        Groth16::<E>::verify_batch(&pvk, &batch, rng).unwrap()
+    };
+
+    assert_eq!(batch(&inputs, &mut rng), Ok(()));
+    assert_eq!(
+        Groth16::<E>::verify_batch(&pvk, &[], &mut rng).unwrap(),
+        Ok(())
+    );
+
+    // The first invalid proof is isolated, wherever it is
+    for bad in [0, n / 2, n - 1] {
+        let mut wrong = inputs.clone();
+        wrong[bad][0] += E::ScalarField::from(1u64);
+        assert_eq!(batch(&wrong, &mut rng), Err(bad));
+    }
+    let mut wrong = inputs.clone();
+    wrong[n - 1][0] = E::ScalarField::zero();
+    wrong[1][0] = E::ScalarField::zero();
+    assert_eq!(batch(&wrong, &mut rng), Err(1));
+
+    // Two wrong proofs that would cancel without the random coefficients
+    let mut swapped = inputs.clone();
+    swapped.swap(0, 1);
+    assert_eq!(batch(&swapped, &mut rng), Err(0));
+
+    // So is a proof with the wrong number of public inputs
+    let mut extra = inputs.clone();
+    extra[2].push(E::ScalarField::zero());
+    assert_eq!(batch(&extra, &mut rng), Err(2));
+}
+
 mod bls12_377 {
-    use super::{test_prove_and_verify, test_rerandomize};
+    use super::{
+        test_malleability, test_prove_and_verify, test_prove_and_verify_with_commitment,
+        test_prove_and_verify_with_context, test_rerandomize, test_rerandomize_with_commitment,
+        test_verify_batch,
+    };
     use ark_bls12_377::Bls12_377;
 
     #[test]
@@ -126,10 +670,44 @@ mod bls12_377 {
         test_prove_and_verify::<Bls12_377>(100);
     }
 
+    #[test]
+    fn prove_and_verify_with_commitment() {
+        test_prove_and_verify_with_commitment::<Bls12_377>(10);
+    }
+
     #[test]
     fn rerandomize() {
         test_rerandomize::<Bls12_377>();
     }
+
+    #[test]
+    fn rerandomize_with_commitment() {
+        test_rerandomize_with_commitment::<Bls12_377>();
+    }
+
+    #[test]
+    fn malleability() {
+        test_malleability::<Bls12_377>();
+    }
+
+    #[test]
+    fn prove_and_verify_with_context() {
+        test_prove_and_verify_with_context::<Bls12_377>();
+    }
+
+    #[test]
+    fn verify_batch() {
+        test_verify_batch::<Bls12_377>(10);
+    }
+}
+
+mod bls12_381 {
+    use super::test_prove_and_verify_with_commitment_and_context;
+
+    #[test]
+    fn prove_and_verify_with_commitment_and_context() {
+        test_prove_and_verify_with_commitment_and_context();
+    }
 }
 
 mod bw6_761 {
@@ -149,11 +727,29 @@ mod bw6_761 {
 }
 
 mod bn_254 {
-    use super::test_prove_and_verify;
+    use super::{
+        test_prove_and_verify, test_prove_and_verify_with_commitment,
+        test_prove_and_verify_with_context, test_verify_batch,
+    };
     use ark_bn254::Bn254;
 
     #[test]
     fn prove_and_verify() {
         test_prove_and_verify::<Bn254>(100);
     }
+
+    #[test]
+    fn prove_and_verify_with_commitment() {
+        test_prove_and_verify_with_commitment::<Bn254>(10);
+    }
+
+    #[test]
+    fn prove_and_verify_with_context() {
+        test_prove_and_verify_with_context::<Bn254>();
+    }
+
+    #[test]
+    fn verify_batch() {
+        test_verify_batch::<Bn254>(10);
+    }
 }
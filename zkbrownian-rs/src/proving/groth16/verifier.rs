@@ -1,12 +1,13 @@
 use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
-use ark_ff::PrimeField;
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
 
 use crate::proving::groth16::{r1cs_to_qap::R1CSToQAP, Groth16};
 
-use super::{PreparedVerifyingKey, Proof, VerifyingKey};
+use super::{CommitCarryingProof, PreparedVerifyingKey, Proof, VerifyingKey};
 
 use ark_relations::gr1cs::Result as R1CSResult;
 
+use ark_std::{rand::Rng, vec::Vec};
 use core::ops::{AddAssign, Neg};
 
 /// Prepare the verifying key `vk` for use in proof verification.
@@ -71,4 +72,133 @@ impl<E: Pairing, QAP: R1CSToQAP> Groth16<E, QAP> {
         let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)?;
         Self::verify_proof_with_prepared_inputs(pvk, proof, &prepared_inputs)
     }
+
+    /// Verify a commit-carrying proof `proof` against the prepared
+    /// verification key `pvk` of its underlying verification key, with
+    /// respect to the instance `public_inputs`. The commitment `D` enters
+    /// the pairing check next to the prepared public inputs.
+    ///
+    /// This check alone does not bind `public_inputs`: `D` is not
+    /// constrained, so `D - delta * gamma_abc_g1[i + 1]` makes the same
+    /// `(A, B, C)` verify with input `i` shifted by `delta`. A proof that `D`
+    /// opens on the commitment bases of the verification key has to be
+    /// checked as well, so outside the crate the only way to verify is
+    /// [`crate::proving::commitment_link::verify_linked_proof`].
+    pub(crate) fn verify_pairing_unlinked(
+        pvk: &PreparedVerifyingKey<E>,
+        proof: &CommitCarryingProof<E>,
+        public_inputs: &[E::ScalarField],
+    ) -> R1CSResult<bool> {
+        let prepared_inputs = Self::prepare_inputs(pvk, public_inputs)? + proof.d;
+        Self::verify_proof_with_prepared_inputs(pvk, &proof.proof, &prepared_inputs)
+    }
+
+    /// Verify the Groth16 proofs `proofs`, each with its public inputs,
+    /// against the prepared verification key `pvk` at once.
+    ///
+    /// With random coefficients `rho_i` sampled via `rng`, the individual
+    /// checks are combined into
+    ///
+    /// ```text
+    /// prod_i e(rho_i * A_i, B_i)
+    ///     = e(alpha, beta)^(sum_i rho_i)
+    ///       * e(sum_i rho_i * P_i, gamma) * e(sum_i rho_i * C_i, delta),
+    /// ```
+    ///
+    /// one multi-Miller loop over `n + 2` pairs and one final
+    /// exponentiation. A batch with an invalid proof passes with probability
+    /// at most `1/|F|`. Proofs with the wrong number of public inputs are
+    /// invalid.
+    ///
+    /// Returns `Ok(Err(i))` if proof `i` is the first invalid one, which is
+    /// found by bisecting the batch with the same coefficients.
+    pub fn verify_batch(
+        pvk: &PreparedVerifyingKey<E>,
+        proofs: &[(&Proof<E>, &[E::ScalarField])],
+        rng: &mut impl Rng,
+    ) -> R1CSResult<Result<(), usize>> {
+        let prepared_inputs = proofs
+            .iter()
+            .map(|(_, public_inputs)| {
+                if public_inputs.len() + 1 == pvk.vk.gamma_abc_g1.len() {
+                    Self::prepare_inputs(pvk, public_inputs).map(Some)
+                } else {
+                    Ok(None)
+                }
+            })
+            .collect::<R1CSResult<Vec<_>>>()?;
+        let rhos: Vec<_> = (0..proofs.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+
+        let first_invalid = Self::first_invalid_in_batch(pvk, proofs, &prepared_inputs, &rhos);
+        Ok(first_invalid.map_or(Ok(()), Err))
+    }
+
+    /// Index of the first proof in the batch for which the combined check
+    /// fails, bisecting from the whole batch down.
+    fn first_invalid_in_batch(
+        pvk: &PreparedVerifyingKey<E>,
+        proofs: &[(&Proof<E>, &[E::ScalarField])],
+        prepared_inputs: &[Option<E::G1>],
+        rhos: &[E::ScalarField],
+    ) -> Option<usize> {
+        if Self::verify_combination(pvk, proofs, prepared_inputs, rhos) {
+            return None;
+        }
+        if proofs.len() == 1 {
+            return Some(0);
+        }
+
+        // The checks are multiplicative, so one of the halves fails as well
+        let mid = proofs.len() / 2;
+        Self::first_invalid_in_batch(pvk, &proofs[..mid], &prepared_inputs[..mid], &rhos[..mid])
+            .or_else(|| {
+                Self::first_invalid_in_batch(
+                    pvk,
+                    &proofs[mid..],
+                    &prepared_inputs[mid..],
+                    &rhos[mid..],
+                )
+                .map(|i| mid + i)
+            })
+    }
+
+    /// The combined check of [`Self::verify_batch`] for the coefficients `rhos`.
+    fn verify_combination(
+        pvk: &PreparedVerifyingKey<E>,
+        proofs: &[(&Proof<E>, &[E::ScalarField])],
+        prepared_inputs: &[Option<E::G1>],
+        rhos: &[E::ScalarField],
+    ) -> bool {
+        let mut scaled_a = Vec::with_capacity(proofs.len());
+        let mut g2: Vec<E::G2Prepared> = Vec::with_capacity(proofs.len() + 2);
+        let mut inputs_sum = E::G1::zero();
+        let mut c_sum = E::G1::zero();
+        let mut rho_sum = E::ScalarField::zero();
+        for (((proof, _), prepared_inputs), rho) in proofs.iter().zip(prepared_inputs).zip(rhos) {
+            let Some(prepared_inputs) = prepared_inputs else {
+                return false;
+            };
+            let rho = *rho;
+            scaled_a.push(proof.a * rho);
+            g2.push(proof.b.into());
+            inputs_sum += *prepared_inputs * rho;
+            c_sum += proof.c * rho;
+            rho_sum += rho;
+        }
+
+        let mut g1: Vec<E::G1Prepared> = E::G1::normalize_batch(&scaled_a)
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        g1.push(inputs_sum.into_affine().into());
+        g2.push(pvk.gamma_g2_neg_pc.clone());
+        g1.push(c_sum.into_affine().into());
+        g2.push(pvk.delta_g2_neg_pc.clone());
+
+        let qap = E::multi_miller_loop(g1, g2);
+        E::final_exponentiation(qap)
+            .is_some_and(|test| test.0 == pvk.alpha_g1_beta_g2.pow(rho_sum.into_bigint()))
+    }
 }
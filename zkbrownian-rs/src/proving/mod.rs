@@ -5,7 +5,19 @@
 pub mod groth16;
 pub mod circuits;
 pub mod constraints;
+pub mod transcript;
+pub mod sigma;
+pub mod pubkey_ops;
+pub mod bridging;
+pub mod range;
+pub mod commitment_link;
 
 pub use groth16::*;
 pub use circuits::*;
 pub use constraints::*;
+pub use transcript::*;
+pub use sigma::*;
+pub use pubkey_ops::*;
+pub use bridging::*;
+pub use range::*;
+pub use commitment_link::*;
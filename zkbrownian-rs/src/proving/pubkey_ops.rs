@@ -0,0 +1,471 @@
+//! π_{4,G2}: public key operations
+//!
+//! A sigma proof (see [`crate::proving::sigma`]) that the forwarder of hop
+//! ν → ν + 1
+//!
+//! 1. owns a public key committed as pk* = sk · G + r · H,
+//! 2. owns the sender key: ppk_{s,1} = sk · ppk_{s,2},
+//! 3. diversified the committed receiver key correctly:
+//!    ppk_{r,2} = d · G and ppk_{r,1} = d · pk_r,
+//! 4. derived the PRF output correctly: (sk + θ) · φ = G1.
+//!
+//! The receiver key is hidden behind C_r = pk_r + r_r · H. Item 3 is linear
+//! in the witnesses once e = d · r_r is introduced:
+//! ppk_{r,1} = d · C_r − e · H. The product e = d · r_r is enforced with an
+//! auxiliary point A = r_r · H_1 and the equation e · H_1 = d · A.
+//! That C_r and pk* commit to registered keys is shown by the membership
+//! proofs; π_{4,G1} ties their G1 commitments to the values used here.
+//!
+//! All equations share one response per witness, so the same sk appears in
+//! pk*, in the sender key and in the PRF.
+
+use crate::crypto::generators::Generators;
+use crate::crypto::poseidon::PoseidonHash;
+use crate::proving::sigma::{LinearRelation, SigmaProof, VerificationTerms};
+use crate::proving::transcript::{PoseidonTranscript, Transcript};
+use crate::types::{
+    DiversifiedPublicKey, G1Point, G2Point, PacketId, PrfOutput, ProtocolError, ProtocolResult,
+    PublicKey, ScalarField, SecretKey, SessionId,
+};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::UniformRand;
+use rand::Rng;
+
+/// Protocol label of the π_{4,G2} transcript
+pub const PUBLIC_KEY_OPS_LABEL: &[u8] = b"zkbrownian/pi_4_g2";
+
+/// Witness indices in the relation
+const SK: usize = 0;
+const PK_RANDOMNESS: usize = 1;
+const DIVERSIFIER: usize = 2;
+const DIVERSIFIER_TIMES_RANDOMNESS: usize = 3;
+const RECEIVER_RANDOMNESS: usize = 4;
+const NUM_WITNESSES: usize = 5;
+
+/// Public inputs of π_{4,G2}
+#[derive(Clone, Debug)]
+pub struct PublicKeyOpsStatement<'a> {
+    pub pid: PacketId,
+    pub sid: SessionId,
+    /// Hop index ν of the sender
+    pub nu: usize,
+    /// θ = Hash(φ_ν, sid, pid, ν)
+    pub theta: ScalarField,
+    /// ppk_ν, owned by the forwarder
+    pub sender_ppk: &'a DiversifiedPublicKey,
+    /// ppk_{ν+1}, addressed to the receiver
+    pub receiver_ppk: &'a DiversifiedPublicKey,
+    /// φ_{ν+1}
+    pub phi: &'a PrfOutput,
+}
+
+/// Secret inputs of π_{4,G2}
+#[derive(Clone, Debug)]
+pub struct PublicKeyOpsWitness<'a> {
+    pub sk: &'a SecretKey,
+    /// Blinding r of pk* = G^sk · H^r
+    pub pk_randomness: ScalarField,
+    /// Receiver public key pk_r
+    pub receiver_pk: &'a PublicKey,
+    /// Blinding r_r of C_r = pk_r · H^{r_r}
+    pub receiver_randomness: ScalarField,
+    /// Diversifier d of ppk_{ν+1}
+    pub d: ScalarField,
+}
+
+/// π_{4,G2}, stored in the `pi_4_g2` slot of [`crate::types::Proof`]
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicKeyOpsProof {
+    /// pk* = G^sk · H^r
+    pub pk_star: G2Point,
+    /// C_r = pk_r · H^{r_r}
+    pub receiver_commitment: G2Point,
+    /// A = H_1^{r_r}
+    pub receiver_aux: G2Point,
+    /// Sigma proof for the linear relation
+    pub sigma: SigmaProof,
+}
+
+impl PublicKeyOpsProof {
+    /// Absorb the whole proof, so that another proof can be bound to it
+    pub fn append_to_transcript<T: Transcript>(&self, transcript: &mut T) {
+        transcript.append_g2(b"pi_4_g2/pk_star", &self.pk_star);
+        transcript.append_g2(b"pi_4_g2/receiver_commitment", &self.receiver_commitment);
+        transcript.append_g2(b"pi_4_g2/receiver_aux", &self.receiver_aux);
+        for commitment in &self.sigma.commitments_g1 {
+            transcript.append_g1(b"pi_4_g2/commitment", commitment);
+        }
+        for commitment in &self.sigma.commitments_g2 {
+            transcript.append_g2(b"pi_4_g2/commitment", commitment);
+        }
+        transcript.append_field_elements(b"pi_4_g2/responses", &self.sigma.responses);
+    }
+}
+
+impl<'a> PublicKeyOpsWitness<'a> {
+    /// Witness with fresh blinding factors r and r_r
+    pub fn new_random<R: Rng>(
+        sk: &'a SecretKey,
+        receiver_pk: &'a PublicKey,
+        d: ScalarField,
+        rng: &mut R,
+    ) -> Self {
+        Self {
+            sk,
+            pk_randomness: ScalarField::rand(rng),
+            receiver_pk,
+            receiver_randomness: ScalarField::rand(rng),
+            d,
+        }
+    }
+}
+
+impl PublicKeyOpsStatement<'_> {
+    /// Reject degenerate keys for which the equations hold for any witness
+    fn check_keys(&self) -> ProtocolResult<()> {
+        if self.sender_ppk.ppk_2.is_zero() || self.receiver_ppk.ppk_2.is_zero() {
+            return Err(ProtocolError::CryptoError(
+                "diversified key with ppk_2 at infinity".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// The linear relation for the given proof commitments
+    pub fn relation(
+        &self,
+        generators: &Generators,
+        pk_star: &G2Point,
+        receiver_commitment: &G2Point,
+        receiver_aux: &G2Point,
+    ) -> LinearRelation {
+        let g = generators.g2_base;
+        let h = generators.g2_generators[0];
+        let h_1 = generators.g2_generators[1];
+
+        let mut relation = LinearRelation::new(NUM_WITNESSES);
+        relation
+            // pk* = sk · G + r · H
+            .add_g2(*pk_star, vec![(SK, g), (PK_RANDOMNESS, h)])
+            // ppk_{s,1} = sk · ppk_{s,2}
+            .add_g2(self.sender_ppk.ppk_1, vec![(SK, self.sender_ppk.ppk_2)])
+            // ppk_{r,2} = d · G
+            .add_g2(self.receiver_ppk.ppk_2, vec![(DIVERSIFIER, g)])
+            // ppk_{r,1} = d · C_r − e · H
+            .add_g2(
+                self.receiver_ppk.ppk_1,
+                vec![
+                    (DIVERSIFIER, *receiver_commitment),
+                    (DIVERSIFIER_TIMES_RANDOMNESS, -h),
+                ],
+            )
+            // A = r_r · H_1
+            .add_g2(*receiver_aux, vec![(RECEIVER_RANDOMNESS, h_1)])
+            // 0 = d · A − e · H_1
+            .add_g2(
+                G2Point::zero(),
+                vec![
+                    (DIVERSIFIER, *receiver_aux),
+                    (DIVERSIFIER_TIMES_RANDOMNESS, -h_1),
+                ],
+            )
+            // G1 − θ · φ = sk · φ
+            .add_g1(
+                (G1Point::generator().into_group() - self.phi.phi * self.theta).into_affine(),
+                vec![(SK, self.phi.phi)],
+            );
+        relation
+    }
+
+    /// Transcript bound to the hop context and the proof commitments
+    pub fn transcript<'h>(
+        &self,
+        hasher: &'h PoseidonHash,
+        proof_points: [&G2Point; 3],
+    ) -> PoseidonTranscript<'h> {
+        let mut transcript = PoseidonTranscript::new(hasher, PUBLIC_KEY_OPS_LABEL);
+        transcript.append_hop_context(
+            self.pid,
+            self.sid,
+            self.nu,
+            self.sender_ppk,
+            self.receiver_ppk,
+            self.phi,
+        );
+        transcript.append_scalar(b"theta", &self.theta);
+        let [pk_star, receiver_commitment, receiver_aux] = proof_points;
+        transcript.append_g2(b"pk_star", pk_star);
+        transcript.append_g2(b"receiver_commitment", receiver_commitment);
+        transcript.append_g2(b"receiver_aux", receiver_aux);
+        transcript
+    }
+}
+
+/// Generate π_{4,G2}
+pub fn prove_public_key_ops<R: Rng>(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &PublicKeyOpsStatement<'_>,
+    witness: &PublicKeyOpsWitness<'_>,
+    rng: &mut R,
+) -> ProtocolResult<PublicKeyOpsProof> {
+    statement.check_keys()?;
+
+    let h = generators.g2_generators[0];
+    let h_1 = generators.g2_generators[1];
+    let sk = witness.sk.sk;
+    let r_r = witness.receiver_randomness;
+
+    let pk_star = (generators.g2_base * sk + h * witness.pk_randomness).into_affine();
+    let receiver_commitment = (witness.receiver_pk.pk.into_group() + h * r_r).into_affine();
+    let receiver_aux = (h_1 * r_r).into_affine();
+
+    let mut witnesses = vec![ScalarField::from(0u64); NUM_WITNESSES];
+    witnesses[SK] = sk;
+    witnesses[PK_RANDOMNESS] = witness.pk_randomness;
+    witnesses[DIVERSIFIER] = witness.d;
+    witnesses[DIVERSIFIER_TIMES_RANDOMNESS] = witness.d * r_r;
+    witnesses[RECEIVER_RANDOMNESS] = r_r;
+
+    let relation = statement.relation(generators, &pk_star, &receiver_commitment, &receiver_aux);
+    let mut transcript =
+        statement.transcript(hasher, [&pk_star, &receiver_commitment, &receiver_aux]);
+    let sigma = relation.prove(&witnesses, &mut transcript, rng)?;
+
+    Ok(PublicKeyOpsProof {
+        pk_star,
+        receiver_commitment,
+        receiver_aux,
+        sigma,
+    })
+}
+
+/// Generate π_{4,G2} with fresh blinding factors
+pub fn prove_public_key_ops_random<R: Rng>(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &PublicKeyOpsStatement<'_>,
+    sk: &SecretKey,
+    receiver_pk: &PublicKey,
+    d: ScalarField,
+    rng: &mut R,
+) -> ProtocolResult<PublicKeyOpsProof> {
+    let witness = PublicKeyOpsWitness::new_random(sk, receiver_pk, d, rng);
+    prove_public_key_ops(hasher, generators, statement, &witness, rng)
+}
+
+/// Recompute the challenge of π_{4,G2} and flatten its verification equations
+pub fn public_key_ops_verification_terms(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &PublicKeyOpsStatement<'_>,
+    proof: &PublicKeyOpsProof,
+) -> Option<VerificationTerms> {
+    statement.check_keys().ok()?;
+
+    let relation = statement.relation(
+        generators,
+        &proof.pk_star,
+        &proof.receiver_commitment,
+        &proof.receiver_aux,
+    );
+    let mut transcript = statement.transcript(
+        hasher,
+        [
+            &proof.pk_star,
+            &proof.receiver_commitment,
+            &proof.receiver_aux,
+        ],
+    );
+    relation.verification_terms(&proof.sigma, &mut transcript)
+}
+
+/// Verify π_{4,G2}
+pub fn verify_public_key_ops(
+    hasher: &PoseidonHash,
+    generators: &Generators,
+    statement: &PublicKeyOpsStatement<'_>,
+    proof: &PublicKeyOpsProof,
+) -> bool {
+    public_key_ops_verification_terms(hasher, generators, statement, proof)
+        .is_some_and(|terms| terms.check())
+}
+
+#[cfg(test)]
+pub(crate) mod test_utils {
+    use super::*;
+    use crate::crypto::curve_ops::{diversify, diversify_with_diversifier, keygen};
+    use crate::crypto::prf::compute_prf;
+    use crate::types::Diversifier;
+    use rand::thread_rng;
+
+    /// A hop with fresh sender and receiver keys
+    pub(crate) struct Fixture {
+        pub(crate) sk: SecretKey,
+        pub(crate) pk: PublicKey,
+        pub(crate) sender_ppk: DiversifiedPublicKey,
+        pub(crate) receiver_pk: PublicKey,
+        pub(crate) receiver_ppk: DiversifiedPublicKey,
+        pub(crate) d: ScalarField,
+        pub(crate) theta: ScalarField,
+        pub(crate) phi: PrfOutput,
+    }
+
+    impl Fixture {
+        pub(crate) fn new() -> Self {
+            let mut rng = thread_rng();
+            let (sk, pk) = keygen(&mut rng);
+            let (_receiver_sk, receiver_pk) = keygen(&mut rng);
+            let (sender_ppk, _) = diversify(&pk, &mut rng);
+            let d = ScalarField::rand(&mut rng);
+            let (receiver_ppk, _) = diversify_with_diversifier(&receiver_pk, &Diversifier { d });
+            let theta = ScalarField::rand(&mut rng);
+            let phi = compute_prf(&theta, &sk, &G1Point::generator()).unwrap();
+
+            Self {
+                sk,
+                pk,
+                sender_ppk,
+                receiver_pk,
+                receiver_ppk,
+                d,
+                theta,
+                phi,
+            }
+        }
+
+        /// Statement of π_{4,G2} for the hop
+        pub(crate) fn statement(&self) -> PublicKeyOpsStatement<'_> {
+            PublicKeyOpsStatement {
+                pid: 1,
+                sid: 100,
+                nu: 2,
+                theta: self.theta,
+                sender_ppk: &self.sender_ppk,
+                receiver_ppk: &self.receiver_ppk,
+                phi: &self.phi,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::Fixture;
+    use super::*;
+    use crate::crypto::curve_ops::keygen;
+    use crate::crypto::generators::protocol_generators;
+    use rand::thread_rng;
+
+    #[test]
+    fn test_public_key_ops_roundtrip() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+        let statement = f.statement();
+
+        let proof = prove_public_key_ops_random(
+            &hasher,
+            generators,
+            &statement,
+            &f.sk,
+            &f.receiver_pk,
+            f.d,
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_public_key_ops(
+            &hasher, generators, &statement, &proof
+        ));
+
+        // Survives serialization into the pi_4_g2 slot
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let decoded = PublicKeyOpsProof::deserialize_compressed(&bytes[..]).unwrap();
+        assert!(verify_public_key_ops(
+            &hasher, generators, &statement, &decoded
+        ));
+
+        // Bound to the hop context
+        let mut other = statement.clone();
+        other.nu = 3;
+        assert!(!verify_public_key_ops(&hasher, generators, &other, &proof));
+    }
+
+    #[test]
+    fn test_public_key_ops_rejects_wrong_witness() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+        let statement = f.statement();
+
+        // A different secret key does not own ppk_ν
+        let (other_sk, _) = keygen(&mut rng);
+        assert!(prove_public_key_ops_random(
+            &hasher,
+            generators,
+            &statement,
+            &other_sk,
+            &f.receiver_pk,
+            f.d,
+            &mut rng,
+        )
+        .is_err());
+
+        // ppk_{ν+1} was not derived from this receiver key
+        let (_, other_pk) = keygen(&mut rng);
+        assert!(prove_public_key_ops_random(
+            &hasher, generators, &statement, &f.sk, &other_pk, f.d, &mut rng,
+        )
+        .is_err());
+
+        // φ for another θ
+        let mut wrong = statement.clone();
+        wrong.theta += ScalarField::from(1u64);
+        assert!(prove_public_key_ops_random(
+            &hasher,
+            generators,
+            &wrong,
+            &f.sk,
+            &f.receiver_pk,
+            f.d,
+            &mut rng,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_public_key_ops_rejects_swapped_commitment() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let generators = protocol_generators();
+        let f = Fixture::new();
+        let statement = f.statement();
+
+        let proof = prove_public_key_ops_random(
+            &hasher,
+            generators,
+            &statement,
+            &f.sk,
+            &f.receiver_pk,
+            f.d,
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.pk_star = (tampered.pk_star.into_group() + generators.g2_base).into_affine();
+        assert!(!verify_public_key_ops(
+            &hasher, generators, &statement, &tampered
+        ));
+
+        let mut tampered = proof;
+        tampered.receiver_aux = generators.g2_generators[1];
+        assert!(!verify_public_key_ops(
+            &hasher, generators, &statement, &tampered
+        ));
+    }
+}
@@ -0,0 +1,468 @@
+//! Range proofs for 32-bit values in G1 Pedersen commitments
+//!
+//! A bit-decomposition proof: the prover commits to every bit of v as
+//! C_i = b_i · G + r_i · H, with the r_i chosen so that Σ 2^i · C_i = C, and
+//! shows for each C_i with a disjunctive (CDS) Schnorr proof that it opens
+//! to 0 or to 1. All bit proofs share one Fiat–Shamir challenge.
+//!
+//! The routing bucket of the receiver is v_1 < ρ ≤ v_2, where v_1 and v_2 are
+//! cumulative weights. [`BucketProof`] shows this for a public ρ and
+//! committed v_1, v_2 with two range proofs on the derived commitments to
+//! ρ − v_1 − 1 and v_2 − ρ. The proof takes any [`Transcript`], so it can be
+//! run on the transcript of π_4 and serialized next to it, as
+//! [`crate::proving::bridging`] does.
+
+use crate::crypto::pedersen::G1PedersenParams;
+use crate::proving::transcript::Transcript;
+use crate::types::{G1Point, ProtocolError, ProtocolResult, ScalarField};
+use ark_bls12_381::G1Projective;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{AdditiveGroup, Field};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{UniformRand, Zero};
+use rand::Rng;
+
+/// Bit length of the committed values
+pub const RANGE_BITS: usize = 32;
+
+/// OR proof that a bit commitment opens to 0 or to 1
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BitProof {
+    /// Commitment of the "bit is 0" branch
+    pub a_0: G1Point,
+    /// Commitment of the "bit is 1" branch
+    pub a_1: G1Point,
+    /// Challenge share of the first branch; the second is c − c_0
+    pub c_0: ScalarField,
+    pub z_0: ScalarField,
+    pub z_1: ScalarField,
+}
+
+/// Proof that a G1 Pedersen commitment opens to a value in [0, 2^32)
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RangeProof {
+    /// C_i = b_i · G + r_i · H
+    pub bit_commitments: Vec<G1Point>,
+    pub bit_proofs: Vec<BitProof>,
+}
+
+/// Proof that committed cumulative weights v_1, v_2 satisfy v_1 < ρ ≤ v_2
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct BucketProof {
+    /// Range proof for ρ − v_1 − 1
+    pub lower: RangeProof,
+    /// Range proof for v_2 − ρ
+    pub upper: RangeProof,
+}
+
+/// Value base G and blinding base H of single-value parameters
+fn bases(params: &G1PedersenParams) -> ProtocolResult<(G1Point, G1Point)> {
+    match params.bases.first() {
+        Some(g) => Ok((*g, params.blinding)),
+        None => Err(ProtocolError::CryptoError(
+            "range proof needs a value base".to_string(),
+        )),
+    }
+}
+
+/// Absorb the statement and the first prover message, derive the challenge
+fn challenge<T: Transcript>(
+    transcript: &mut T,
+    params: &G1PedersenParams,
+    commitment: &G1Point,
+    proof_points: impl Iterator<Item = (G1Point, G1Point, G1Point)>,
+) -> ScalarField {
+    transcript.append_g1(b"range/base", &params.bases[0]);
+    transcript.append_g1(b"range/blinding", &params.blinding);
+    transcript.append_g1(b"range/commitment", commitment);
+    for (bit_commitment, a_0, a_1) in proof_points {
+        transcript.append_g1(b"range/bit", &bit_commitment);
+        transcript.append_g1(b"range/a_0", &a_0);
+        transcript.append_g1(b"range/a_1", &a_1);
+    }
+    transcript.challenge_scalar(b"range/challenge")
+}
+
+/// Prove that `commitment` = v · G + r · H with v < 2^32
+///
+/// G is the first base of `params` and H its blinding base.
+pub fn prove_range<T: Transcript, R: Rng>(
+    params: &G1PedersenParams,
+    commitment: &G1Point,
+    value: u64,
+    randomness: &ScalarField,
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<RangeProof> {
+    let (g, h) = bases(params)?;
+    if value >> RANGE_BITS != 0 {
+        return Err(ProtocolError::CryptoError(format!(
+            "value {} does not fit in {} bits",
+            value, RANGE_BITS
+        )));
+    }
+    if (g * ScalarField::from(value) + h * randomness).into_affine() != *commitment {
+        return Err(ProtocolError::CryptoError(
+            "opening does not match the commitment".to_string(),
+        ));
+    }
+
+    // r_i random except the top one, which makes Σ 2^i · r_i = r
+    let mut bit_randomness: Vec<ScalarField> = (0..RANGE_BITS - 1)
+        .map(|_| ScalarField::rand(rng))
+        .collect();
+    let mut rest = *randomness;
+    let mut power = ScalarField::from(1u64);
+    for r_i in &bit_randomness {
+        rest -= power * r_i;
+        power.double_in_place();
+    }
+    let inverse = power.inverse().expect("powers of two are invertible");
+    bit_randomness.push(rest * inverse);
+
+    let bits: Vec<bool> = (0..RANGE_BITS).map(|i| (value >> i) & 1 == 1).collect();
+    let bit_commitments = G1Projective::normalize_batch(
+        &bits
+            .iter()
+            .zip(&bit_randomness)
+            .map(|(&bit, r_i)| {
+                let blinded = h * r_i;
+                if bit {
+                    blinded + g
+                } else {
+                    blinded
+                }
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    // Real branch: A_b = k · H. Simulated branch: pick c', z' and set
+    // A = z' · H − c' · Y, with Y = C_i for bit 0 and C_i − G for bit 1
+    let mut nonces = Vec::with_capacity(RANGE_BITS);
+    let mut simulated = Vec::with_capacity(RANGE_BITS);
+    let mut first_messages = Vec::with_capacity(RANGE_BITS);
+    for (&bit, c_i) in bits.iter().zip(&bit_commitments) {
+        let k = ScalarField::rand(rng);
+        let c_sim = ScalarField::rand(rng);
+        let z_sim = ScalarField::rand(rng);
+        let real = (h * k).into_affine();
+        let fake = if bit {
+            // Simulate "bit is 0" for Y = C_i
+            (h * z_sim - *c_i * c_sim).into_affine()
+        } else {
+            // Simulate "bit is 1" for Y = C_i − G
+            (h * z_sim - (c_i.into_group() - g) * c_sim).into_affine()
+        };
+        let (a_0, a_1) = if bit { (fake, real) } else { (real, fake) };
+        nonces.push(k);
+        simulated.push((c_sim, z_sim));
+        first_messages.push((a_0, a_1));
+    }
+
+    let c = challenge(
+        transcript,
+        params,
+        commitment,
+        bit_commitments
+            .iter()
+            .zip(&first_messages)
+            .map(|(c_i, (a_0, a_1))| (*c_i, *a_0, *a_1)),
+    );
+
+    let bit_proofs = bits
+        .iter()
+        .zip(&bit_randomness)
+        .zip(nonces.iter().zip(&simulated))
+        .zip(&first_messages)
+        .map(|(((&bit, r_i), (k, &(c_sim, z_sim))), &(a_0, a_1))| {
+            let c_real = c - c_sim;
+            let z_real = *k + c_real * r_i;
+            if bit {
+                BitProof {
+                    a_0,
+                    a_1,
+                    c_0: c_sim,
+                    z_0: z_sim,
+                    z_1: z_real,
+                }
+            } else {
+                BitProof {
+                    a_0,
+                    a_1,
+                    c_0: c_real,
+                    z_0: z_real,
+                    z_1: z_sim,
+                }
+            }
+        })
+        .collect();
+
+    Ok(RangeProof {
+        bit_commitments,
+        bit_proofs,
+    })
+}
+
+/// Verify that `commitment` opens to a value in [0, 2^32)
+pub fn verify_range<T: Transcript>(
+    params: &G1PedersenParams,
+    commitment: &G1Point,
+    proof: &RangeProof,
+    transcript: &mut T,
+) -> bool {
+    let Ok((g, h)) = bases(params) else {
+        return false;
+    };
+    if proof.bit_commitments.len() != RANGE_BITS || proof.bit_proofs.len() != RANGE_BITS {
+        return false;
+    }
+
+    // Σ 2^i · C_i = C
+    let mut sum = G1Projective::zero();
+    for c_i in proof.bit_commitments.iter().rev() {
+        sum.double_in_place();
+        sum += c_i;
+    }
+    if sum.into_affine() != *commitment {
+        return false;
+    }
+
+    let c = challenge(
+        transcript,
+        params,
+        commitment,
+        proof
+            .bit_commitments
+            .iter()
+            .zip(&proof.bit_proofs)
+            .map(|(c_i, bit)| (*c_i, bit.a_0, bit.a_1)),
+    );
+
+    proof
+        .bit_commitments
+        .iter()
+        .zip(&proof.bit_proofs)
+        .all(|(c_i, bit)| {
+            let c_1 = c - bit.c_0;
+            // z_0 · H = A_0 + c_0 · C_i
+            let zero_branch = h * bit.z_0 == bit.a_0.into_group() + *c_i * bit.c_0;
+            // z_1 · H = A_1 + c_1 · (C_i − G)
+            let one_branch = h * bit.z_1 == bit.a_1.into_group() + (c_i.into_group() - g) * c_1;
+            zero_branch && one_branch
+        })
+}
+
+/// Commitments to ρ − v_1 − 1 and v_2 − ρ derived from C_{v_1} and C_{v_2}
+fn bucket_commitments(
+    params: &G1PedersenParams,
+    rho: u64,
+    lower_bound: &G1Point,
+    upper_bound: &G1Point,
+) -> ProtocolResult<(G1Point, G1Point)> {
+    let (g, _) = bases(params)?;
+    let rho = ScalarField::from(rho);
+    let lower = (g * (rho - ScalarField::from(1u64)) - lower_bound.into_group()).into_affine();
+    let upper = (upper_bound.into_group() - g * rho).into_affine();
+    Ok((lower, upper))
+}
+
+/// Prove v_1 < ρ ≤ v_2 for C_{v_1} = v_1 · G + r_1 · H and C_{v_2} = v_2 · G + r_2 · H
+///
+/// ρ is a `u64` so that the top bucket can end at v_2 = 2^32.
+#[allow(clippy::too_many_arguments)]
+pub fn prove_bucket<T: Transcript, R: Rng>(
+    params: &G1PedersenParams,
+    rho: u64,
+    lower_bound: &G1Point,
+    (v_1, r_1): (u64, &ScalarField),
+    upper_bound: &G1Point,
+    (v_2, r_2): (u64, &ScalarField),
+    transcript: &mut T,
+    rng: &mut R,
+) -> ProtocolResult<BucketProof> {
+    if v_1 >= rho || rho > v_2 {
+        return Err(ProtocolError::InvalidWeightSelection);
+    }
+
+    let (lower, upper) = bucket_commitments(params, rho, lower_bound, upper_bound)?;
+    transcript.append_u64(b"bucket/rho", rho);
+    let lower = prove_range(params, &lower, rho - v_1 - 1, &-*r_1, transcript, rng)?;
+    let upper = prove_range(params, &upper, v_2 - rho, r_2, transcript, rng)?;
+    Ok(BucketProof { lower, upper })
+}
+
+/// Verify v_1 < ρ ≤ v_2 for the committed bounds
+pub fn verify_bucket<T: Transcript>(
+    params: &G1PedersenParams,
+    rho: u64,
+    lower_bound: &G1Point,
+    upper_bound: &G1Point,
+    proof: &BucketProof,
+    transcript: &mut T,
+) -> bool {
+    let Ok((lower, upper)) = bucket_commitments(params, rho, lower_bound, upper_bound) else {
+        return false;
+    };
+    transcript.append_u64(b"bucket/rho", rho);
+    verify_range(params, &lower, &proof.lower, transcript)
+        && verify_range(params, &upper, &proof.upper, transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generators::protocol_generators;
+    use crate::proving::transcript::Sha256Transcript;
+    use rand::thread_rng;
+
+    fn params() -> G1PedersenParams {
+        G1PedersenParams::from_generators(protocol_generators(), 1).unwrap()
+    }
+
+    fn commit(params: &G1PedersenParams, value: u64, r: &ScalarField) -> G1Point {
+        params.commit(&[ScalarField::from(value)], r).unwrap().point
+    }
+
+    #[test]
+    fn test_range_proof_roundtrip() {
+        let mut rng = thread_rng();
+        let params = params();
+
+        for value in [0, 1, 12345, u32::MAX as u64] {
+            let r = ScalarField::rand(&mut rng);
+            let commitment = commit(&params, value, &r);
+            let proof = prove_range(
+                &params,
+                &commitment,
+                value,
+                &r,
+                &mut Sha256Transcript::new(b"test"),
+                &mut rng,
+            )
+            .unwrap();
+            assert!(verify_range(
+                &params,
+                &commitment,
+                &proof,
+                &mut Sha256Transcript::new(b"test")
+            ));
+
+            let mut bytes = Vec::new();
+            proof.serialize_compressed(&mut bytes).unwrap();
+            let decoded = RangeProof::deserialize_compressed(&bytes[..]).unwrap();
+            assert_eq!(decoded, proof);
+
+            // Bound to the transcript it was produced on
+            assert!(!verify_range(
+                &params,
+                &commitment,
+                &proof,
+                &mut Sha256Transcript::new(b"other")
+            ));
+        }
+    }
+
+    #[test]
+    fn test_range_proof_rejects_out_of_range() {
+        let mut rng = thread_rng();
+        let params = params();
+        let r = ScalarField::rand(&mut rng);
+
+        // The prover refuses values of 33 bits and wrong openings
+        let value = 1u64 << 32;
+        let commitment = commit(&params, value, &r);
+        let mut transcript = Sha256Transcript::new(b"test");
+        assert!(prove_range(&params, &commitment, value, &r, &mut transcript, &mut rng).is_err());
+        assert!(prove_range(&params, &commitment, 7, &r, &mut transcript, &mut rng).is_err());
+
+        // A proof for one commitment does not verify for a shifted one
+        let commitment = commit(&params, 7, &r);
+        let proof = prove_range(
+            &params,
+            &commitment,
+            7,
+            &r,
+            &mut Sha256Transcript::new(b"test"),
+            &mut rng,
+        )
+        .unwrap();
+        let shifted = (commitment.into_group() + params.bases[0]).into_affine();
+        assert!(!verify_range(
+            &params,
+            &shifted,
+            &proof,
+            &mut Sha256Transcript::new(b"test")
+        ));
+
+        // Moving weight between bit commitments keeps Σ 2^i · C_i but leaves
+        // C_0 opening to 3 (7 = 1 + 2 + 4), which its OR proof cannot cover
+        let mut forged = proof.clone();
+        forged.bit_commitments[0] = (forged.bit_commitments[0].into_group()
+            + params.bases[0] * ScalarField::from(2u64))
+        .into_affine();
+        forged.bit_commitments[1] =
+            (forged.bit_commitments[1].into_group() - params.bases[0]).into_affine();
+        assert!(!verify_range(
+            &params,
+            &commitment,
+            &forged,
+            &mut Sha256Transcript::new(b"test")
+        ));
+    }
+
+    #[test]
+    fn test_bucket_proof() {
+        let mut rng = thread_rng();
+        let params = params();
+        let (v_1, v_2) = (1_000u64, 3_000u64);
+        let (r_1, r_2) = (ScalarField::rand(&mut rng), ScalarField::rand(&mut rng));
+        let c_1 = commit(&params, v_1, &r_1);
+        let c_2 = commit(&params, v_2, &r_2);
+
+        for rho in [1_001u64, 2_500, 3_000] {
+            let proof = prove_bucket(
+                &params,
+                rho,
+                &c_1,
+                (v_1, &r_1),
+                &c_2,
+                (v_2, &r_2),
+                &mut Sha256Transcript::new(b"test"),
+                &mut rng,
+            )
+            .unwrap();
+            assert!(verify_bucket(
+                &params,
+                rho,
+                &c_1,
+                &c_2,
+                &proof,
+                &mut Sha256Transcript::new(b"test")
+            ));
+            // The same proof does not cover another ρ
+            assert!(!verify_bucket(
+                &params,
+                rho + 1,
+                &c_1,
+                &c_2,
+                &proof,
+                &mut Sha256Transcript::new(b"test")
+            ));
+        }
+
+        // ρ = v_1 is the previous bucket, ρ = v_2 + 1 the next one
+        for rho in [1_000u64, 3_001] {
+            assert!(prove_bucket(
+                &params,
+                rho,
+                &c_1,
+                (v_1, &r_1),
+                &c_2,
+                (v_2, &r_2),
+                &mut Sha256Transcript::new(b"test"),
+                &mut rng,
+            )
+            .is_err());
+        }
+    }
+}
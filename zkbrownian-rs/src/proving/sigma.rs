@@ -0,0 +1,422 @@
+//! Sigma protocols for linear relations over G1 and G2
+//!
+//! A [`LinearRelation`] is a set of equations X_j = Σ_i w_i · B_ij over a
+//! shared witness vector w, where each equation lives in G1 or in G2. The
+//! Schnorr-style proof commits to T_j = Σ_i k_i · B_ij, derives the challenge
+//! c from a [`Transcript`] and answers with z_i = k_i + c · w_i. The verifier
+//! checks Σ_i z_i · B_ij = T_j + c · X_j for every equation.
+//!
+//! There is exactly one response per witness, no matter how many equations
+//! (or groups) it appears in, so the proof also shows that the same scalar
+//! was used in G1 and in G2. That is what the bridging proofs rely on.
+
+use crate::proving::transcript::Transcript;
+use crate::types::{G1Point, G2Point, ProtocolError, ProtocolResult, ScalarField};
+use ark_bls12_381::{G1Projective, G2Projective};
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{UniformRand, Zero};
+use rand::Rng;
+
+/// One equation X = Σ w_i · B_i
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearEquation<C: CurveGroup<ScalarField = ScalarField>> {
+    /// The public image X
+    pub image: C::Affine,
+    /// Pairs (witness index i, base B_i)
+    pub terms: Vec<(usize, C::Affine)>,
+}
+
+impl<C: CurveGroup<ScalarField = ScalarField>> LinearEquation<C> {
+    /// Σ s_i · B_i for per-witness scalars s
+    fn evaluate(&self, scalars: &[ScalarField]) -> C {
+        let (indices, bases): (Vec<usize>, Vec<C::Affine>) = self.terms.iter().copied().unzip();
+        let scalars: Vec<ScalarField> = indices.iter().map(|&i| scalars[i]).collect();
+        C::msm_unchecked(&bases, &scalars)
+    }
+
+    /// Bases and scalars whose multi-scalar product is zero iff the equation
+    /// verifies: Σ z_i · B_i − c · X − T
+    fn verification_terms(
+        &self,
+        responses: &[ScalarField],
+        challenge: ScalarField,
+        commitment: C::Affine,
+    ) -> (Vec<C::Affine>, Vec<ScalarField>) {
+        let mut bases = Vec::with_capacity(self.terms.len() + 2);
+        let mut scalars = Vec::with_capacity(self.terms.len() + 2);
+        for &(i, base) in &self.terms {
+            bases.push(base);
+            scalars.push(responses[i]);
+        }
+        bases.push(self.image);
+        scalars.push(-challenge);
+        bases.push(commitment);
+        scalars.push(-ScalarField::from(1u64));
+        (bases, scalars)
+    }
+}
+
+/// A set of linear equations over G1 and G2 sharing one witness vector
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearRelation {
+    /// Number of witness scalars
+    pub num_witnesses: usize,
+    /// Equations in G1
+    pub g1: Vec<LinearEquation<G1Projective>>,
+    /// Equations in G2
+    pub g2: Vec<LinearEquation<G2Projective>>,
+}
+
+/// Non-interactive proof for a [`LinearRelation`]
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SigmaProof {
+    /// Commitments T_j for the G1 equations
+    pub commitments_g1: Vec<G1Point>,
+    /// Commitments T_j for the G2 equations
+    pub commitments_g2: Vec<G2Point>,
+    /// One response z_i per witness
+    pub responses: Vec<ScalarField>,
+}
+
+/// Verification equations of a proof, flattened for multi-scalar
+/// multiplication
+///
+/// Each entry is a list of bases and scalars whose product must be zero.
+/// A batch verifier can scale every entry by a random weight and run one
+/// MSM per group over the concatenation.
+#[derive(Clone, Debug, Default)]
+pub struct VerificationTerms {
+    /// One entry per G1 equation
+    pub g1: Vec<(Vec<G1Point>, Vec<ScalarField>)>,
+    /// One entry per G2 equation
+    pub g2: Vec<(Vec<G2Point>, Vec<ScalarField>)>,
+}
+
+impl LinearRelation {
+    /// An empty relation over `num_witnesses` witnesses
+    pub fn new(num_witnesses: usize) -> Self {
+        Self {
+            num_witnesses,
+            g1: Vec::new(),
+            g2: Vec::new(),
+        }
+    }
+
+    /// Add an equation X = Σ w_i · B_i in G1
+    pub fn add_g1(&mut self, image: G1Point, terms: Vec<(usize, G1Point)>) -> &mut Self {
+        self.g1.push(LinearEquation { image, terms });
+        self
+    }
+
+    /// Add an equation X = Σ w_i · B_i in G2
+    pub fn add_g2(&mut self, image: G2Point, terms: Vec<(usize, G2Point)>) -> &mut Self {
+        self.g2.push(LinearEquation { image, terms });
+        self
+    }
+
+    /// Check that every term refers to an existing witness
+    fn check_indices(&self) -> ProtocolResult<()> {
+        let g1 = self.g1.iter().flat_map(|eq| eq.terms.iter().map(|(i, _)| *i));
+        let g2 = self.g2.iter().flat_map(|eq| eq.terms.iter().map(|(i, _)| *i));
+        match g1.chain(g2).find(|&i| i >= self.num_witnesses) {
+            Some(i) => Err(ProtocolError::CryptoError(format!(
+                "witness index {} out of range ({} witnesses)",
+                i, self.num_witnesses
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether `witnesses` satisfy every equation
+    pub fn is_satisfied(&self, witnesses: &[ScalarField]) -> bool {
+        witnesses.len() == self.num_witnesses
+            && self.check_indices().is_ok()
+            && self
+                .g1
+                .iter()
+                .all(|eq| eq.evaluate(witnesses).into_affine() == eq.image)
+            && self
+                .g2
+                .iter()
+                .all(|eq| eq.evaluate(witnesses).into_affine() == eq.image)
+    }
+
+    /// Absorb the statement: all bases and images
+    pub fn append_to_transcript<T: Transcript>(&self, transcript: &mut T) {
+        transcript.append_u64(b"sigma/witnesses", self.num_witnesses as u64);
+        transcript.append_u64(b"sigma/g1-equations", self.g1.len() as u64);
+        for eq in &self.g1 {
+            transcript.append_g1(b"sigma/image", &eq.image);
+            transcript.append_u64(b"sigma/terms", eq.terms.len() as u64);
+            for (i, base) in &eq.terms {
+                transcript.append_u64(b"sigma/index", *i as u64);
+                transcript.append_g1(b"sigma/base", base);
+            }
+        }
+        transcript.append_u64(b"sigma/g2-equations", self.g2.len() as u64);
+        for eq in &self.g2 {
+            transcript.append_g2(b"sigma/image", &eq.image);
+            transcript.append_u64(b"sigma/terms", eq.terms.len() as u64);
+            for (i, base) in &eq.terms {
+                transcript.append_u64(b"sigma/index", *i as u64);
+                transcript.append_g2(b"sigma/base", base);
+            }
+        }
+    }
+
+    /// Fiat–Shamir challenge for the given first-round commitments
+    fn challenge<T: Transcript>(
+        &self,
+        commitments_g1: &[G1Point],
+        commitments_g2: &[G2Point],
+        transcript: &mut T,
+    ) -> ScalarField {
+        self.append_to_transcript(transcript);
+        for commitment in commitments_g1 {
+            transcript.append_g1(b"sigma/commitment", commitment);
+        }
+        for commitment in commitments_g2 {
+            transcript.append_g2(b"sigma/commitment", commitment);
+        }
+        transcript.challenge_scalar(b"sigma/challenge")
+    }
+
+    /// Prove knowledge of `witnesses` satisfying the relation
+    ///
+    /// The transcript should already hold the context the proof is bound to;
+    /// the statement itself is absorbed here.
+    pub fn prove<T: Transcript, R: Rng>(
+        &self,
+        witnesses: &[ScalarField],
+        transcript: &mut T,
+        rng: &mut R,
+    ) -> ProtocolResult<SigmaProof> {
+        if !self.is_satisfied(witnesses) {
+            return Err(ProtocolError::CryptoError(
+                "witnesses do not satisfy the sigma relation".to_string(),
+            ));
+        }
+
+        let nonces: Vec<ScalarField> = (0..self.num_witnesses)
+            .map(|_| ScalarField::rand(rng))
+            .collect();
+        let commitments_g1 = G1Projective::normalize_batch(
+            &self.g1.iter().map(|eq| eq.evaluate(&nonces)).collect::<Vec<_>>(),
+        );
+        let commitments_g2 = G2Projective::normalize_batch(
+            &self.g2.iter().map(|eq| eq.evaluate(&nonces)).collect::<Vec<_>>(),
+        );
+
+        let challenge = self.challenge(&commitments_g1, &commitments_g2, transcript);
+        let responses = nonces
+            .iter()
+            .zip(witnesses)
+            .map(|(k, w)| *k + challenge * w)
+            .collect();
+
+        Ok(SigmaProof {
+            commitments_g1,
+            commitments_g2,
+            responses,
+        })
+    }
+
+    /// Recompute the challenge and flatten the verification equations
+    ///
+    /// Returns `None` if the proof does not have the shape of the relation.
+    pub fn verification_terms<T: Transcript>(
+        &self,
+        proof: &SigmaProof,
+        transcript: &mut T,
+    ) -> Option<VerificationTerms> {
+        if proof.commitments_g1.len() != self.g1.len()
+            || proof.commitments_g2.len() != self.g2.len()
+            || proof.responses.len() != self.num_witnesses
+            || self.check_indices().is_err()
+        {
+            return None;
+        }
+
+        let challenge = self.challenge(&proof.commitments_g1, &proof.commitments_g2, transcript);
+        Some(VerificationTerms {
+            g1: self
+                .g1
+                .iter()
+                .zip(&proof.commitments_g1)
+                .map(|(eq, t)| eq.verification_terms(&proof.responses, challenge, *t))
+                .collect(),
+            g2: self
+                .g2
+                .iter()
+                .zip(&proof.commitments_g2)
+                .map(|(eq, t)| eq.verification_terms(&proof.responses, challenge, *t))
+                .collect(),
+        })
+    }
+
+    /// Verify a proof against the relation
+    pub fn verify<T: Transcript>(&self, proof: &SigmaProof, transcript: &mut T) -> bool {
+        self.verification_terms(proof, transcript)
+            .is_some_and(|terms| terms.check())
+    }
+}
+
+impl VerificationTerms {
+    /// Check every equation separately
+    pub fn check(&self) -> bool {
+        self.g1
+            .iter()
+            .all(|(bases, scalars)| G1Projective::msm_unchecked(bases, scalars).is_zero())
+            && self
+                .g2
+                .iter()
+                .all(|(bases, scalars)| G2Projective::msm_unchecked(bases, scalars).is_zero())
+    }
+
+    /// Check the equations of many proofs with one MSM per group
+    ///
+    /// Every equation is scaled by an independent 128-bit random weight, so
+    /// a batch containing a false equation passes with probability at most
+    /// 2^-128. On failure the caller has to fall back to [`Self::check`] to
+    /// find the offending proof.
+    pub fn batch_check<'a, R: Rng>(
+        terms: impl IntoIterator<Item = &'a VerificationTerms>,
+        rng: &mut R,
+    ) -> bool {
+        let mut g1_bases = Vec::new();
+        let mut g1_scalars = Vec::new();
+        let mut g2_bases = Vec::new();
+        let mut g2_scalars = Vec::new();
+
+        for entry in terms {
+            for (bases, scalars) in &entry.g1 {
+                let weight = ScalarField::from(rng.gen::<u128>());
+                g1_bases.extend_from_slice(bases);
+                g1_scalars.extend(scalars.iter().map(|s| *s * weight));
+            }
+            for (bases, scalars) in &entry.g2 {
+                let weight = ScalarField::from(rng.gen::<u128>());
+                g2_bases.extend_from_slice(bases);
+                g2_scalars.extend(scalars.iter().map(|s| *s * weight));
+            }
+        }
+
+        G1Projective::msm_unchecked(&g1_bases, &g1_scalars).is_zero()
+            && G2Projective::msm_unchecked(&g2_bases, &g2_scalars).is_zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generators::Generators;
+    use crate::crypto::PoseidonHash;
+    use crate::proving::transcript::{PoseidonTranscript, Sha256Transcript};
+    use rand::thread_rng;
+
+    /// Pedersen opening in G1 and the same first value as an exponent in G2:
+    /// C = w_0 G_0 + w_1 G_1 (G1), P = w_0 H (G2)
+    fn cross_group_relation(gens: &Generators, w: &[ScalarField]) -> LinearRelation {
+        let (g0, g1, h) = (gens.g1_generators[0], gens.g1_generators[1], gens.g2_generators[0]);
+        let c = (g0 * w[0] + g1 * w[1]).into_affine();
+        let p = (h * w[0]).into_affine();
+
+        let mut relation = LinearRelation::new(2);
+        relation.add_g1(c, vec![(0, g0), (1, g1)]).add_g2(p, vec![(0, h)]);
+        relation
+    }
+
+    #[test]
+    fn test_prove_and_verify() {
+        let mut rng = thread_rng();
+        let gens = Generators::from_seed(b"sigma-test", 2, 1).unwrap();
+        let hasher = PoseidonHash::new();
+        let w = vec![ScalarField::rand(&mut rng), ScalarField::rand(&mut rng)];
+        let relation = cross_group_relation(&gens, &w);
+
+        let proof = relation
+            .prove(&w, &mut PoseidonTranscript::new(&hasher, b"test"), &mut rng)
+            .unwrap();
+        assert!(relation.verify(&proof, &mut PoseidonTranscript::new(&hasher, b"test")));
+
+        // The challenge depends on the transcript context
+        assert!(!relation.verify(&proof, &mut PoseidonTranscript::new(&hasher, b"other")));
+
+        let proof = relation
+            .prove(&w, &mut Sha256Transcript::new(b"test"), &mut rng)
+            .unwrap();
+        assert!(relation.verify(&proof, &mut Sha256Transcript::new(b"test")));
+    }
+
+    #[test]
+    fn test_rejects_tampered_proof() {
+        let mut rng = thread_rng();
+        let gens = Generators::from_seed(b"sigma-test", 2, 1).unwrap();
+        let w = vec![ScalarField::rand(&mut rng), ScalarField::rand(&mut rng)];
+        let relation = cross_group_relation(&gens, &w);
+
+        let proof = relation
+            .prove(&w, &mut Sha256Transcript::new(b"test"), &mut rng)
+            .unwrap();
+
+        let mut tampered = proof.clone();
+        tampered.responses[0] += ScalarField::from(1u64);
+        assert!(!relation.verify(&tampered, &mut Sha256Transcript::new(b"test")));
+
+        let mut tampered = proof.clone();
+        tampered.commitments_g2[0] = gens.g2_base;
+        assert!(!relation.verify(&tampered, &mut Sha256Transcript::new(b"test")));
+
+        let mut tampered = proof;
+        tampered.responses.pop();
+        assert!(!relation.verify(&tampered, &mut Sha256Transcript::new(b"test")));
+    }
+
+    #[test]
+    fn test_shared_witness_across_groups() {
+        let mut rng = thread_rng();
+        let gens = Generators::from_seed(b"sigma-test", 2, 1).unwrap();
+        let w = vec![ScalarField::rand(&mut rng), ScalarField::rand(&mut rng)];
+
+        // The G2 image uses a different exponent than the G1 commitment
+        let mut relation = cross_group_relation(&gens, &w);
+        relation.g2[0].image = (gens.g2_generators[0] * ScalarField::rand(&mut rng)).into_affine();
+
+        assert!(!relation.is_satisfied(&w));
+        assert!(relation
+            .prove(&w, &mut Sha256Transcript::new(b"test"), &mut rng)
+            .is_err());
+
+        // Out-of-range witness indices are rejected
+        let mut relation = LinearRelation::new(1);
+        relation.add_g1(gens.g1_base, vec![(1, gens.g1_base)]);
+        assert!(!relation.is_satisfied(&[ScalarField::from(1u64)]));
+    }
+
+    #[test]
+    fn test_batch_check() {
+        let mut rng = thread_rng();
+        let gens = Generators::from_seed(b"sigma-test", 2, 1).unwrap();
+
+        let mut terms: Vec<VerificationTerms> = (0..4)
+            .map(|_| {
+                let w = vec![ScalarField::rand(&mut rng), ScalarField::rand(&mut rng)];
+                let relation = cross_group_relation(&gens, &w);
+                let proof = relation
+                    .prove(&w, &mut Sha256Transcript::new(b"test"), &mut rng)
+                    .unwrap();
+                relation
+                    .verification_terms(&proof, &mut Sha256Transcript::new(b"test"))
+                    .unwrap()
+            })
+            .collect();
+        assert!(VerificationTerms::batch_check(&terms, &mut rng));
+        assert!(VerificationTerms::batch_check(&terms[..0], &mut rng));
+
+        // One false G2 equation spoils the batch
+        terms[2].g2[0].1[0] += ScalarField::from(1u64);
+        assert!(!terms[2].check());
+        assert!(!VerificationTerms::batch_check(&terms, &mut rng));
+    }
+}
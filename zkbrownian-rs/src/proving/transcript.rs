@@ -0,0 +1,300 @@
+//! Fiat–Shamir transcripts shared by the proof components
+//!
+//! Every component of π_{ν+1} absorbs the same hop context (pid, sid, ν,
+//! ppk_ν, ppk_{ν+1}, φ_{ν+1}) before its own messages, so a proof cannot be
+//! replayed for another hop or message. Values are absorbed as scalar field
+//! elements with a label and a length prefix: points go through the
+//! canonical encodings in [`crate::crypto::encoding`], which keeps the
+//! Poseidon transcript reproducible inside a circuit.
+//!
+//! [`PoseidonTranscript`] is the default. [`Sha256Transcript`] absorbs the
+//! same field elements into SHA-256 for components that are never verified
+//! in-circuit.
+
+use crate::crypto::encoding::{encode_g1, encode_g2};
+use crate::crypto::poseidon::{DomainTag, PoseidonHash, PoseidonSponge};
+use crate::proving::groth16::{Proof as Groth16Proof, VerifyingKey};
+use crate::types::{
+    DiversifiedPublicKey, G1Point, G2Point, PacketId, PairingEngine, PrfOutput, ScalarField,
+    SessionId,
+};
+use ark_ff::{BigInteger, PrimeField};
+use sha2::{Digest, Sha256};
+
+/// Map a label to a field element (labels should be at most 31 bytes)
+fn label_to_field(label: &[u8]) -> ScalarField {
+    ScalarField::from_le_bytes_mod_order(label)
+}
+
+/// A Fiat–Shamir transcript
+///
+/// Implementations only need to absorb labelled field elements and derive
+/// challenges; the typed append methods are provided on top of that.
+pub trait Transcript {
+    /// Absorb a labelled, length-prefixed sequence of field elements
+    fn append_field_elements(&mut self, label: &'static [u8], elements: &[ScalarField]);
+
+    /// Derive a challenge from everything absorbed so far
+    ///
+    /// The challenge is absorbed as well, so consecutive challenges differ.
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> ScalarField;
+
+    /// Absorb a scalar
+    fn append_scalar(&mut self, label: &'static [u8], scalar: &ScalarField) {
+        self.append_field_elements(label, &[*scalar]);
+    }
+
+    /// Absorb an integer
+    fn append_u64(&mut self, label: &'static [u8], value: u64) {
+        self.append_field_elements(label, &[ScalarField::from(value)]);
+    }
+
+    /// Absorb a G1 point
+    fn append_g1(&mut self, label: &'static [u8], point: &G1Point) {
+        self.append_field_elements(label, &encode_g1(point));
+    }
+
+    /// Absorb a G2 point
+    fn append_g2(&mut self, label: &'static [u8], point: &G2Point) {
+        self.append_field_elements(label, &encode_g2(point));
+    }
+
+    /// Absorb a Groth16 proof (A, B, C)
+    fn append_groth16_proof(&mut self, label: &'static [u8], proof: &Groth16Proof<PairingEngine>) {
+        let mut elements = encode_g1(&proof.a).to_vec();
+        elements.extend_from_slice(&encode_g2(&proof.b));
+        elements.extend_from_slice(&encode_g1(&proof.c));
+        self.append_field_elements(label, &elements);
+    }
+
+    /// Absorb a Groth16 verifying key
+    fn append_groth16_vk(&mut self, label: &'static [u8], vk: &VerifyingKey<PairingEngine>) {
+        let mut elements = encode_g1(&vk.alpha_g1).to_vec();
+        for point in [&vk.beta_g2, &vk.gamma_g2, &vk.delta_g2] {
+            elements.extend_from_slice(&encode_g2(point));
+        }
+        for point in &vk.gamma_abc_g1 {
+            elements.extend_from_slice(&encode_g1(point));
+        }
+        self.append_field_elements(label, &elements);
+    }
+
+    /// Absorb the context of hop ν → ν + 1
+    fn append_hop_context(
+        &mut self,
+        pid: PacketId,
+        sid: SessionId,
+        nu: usize,
+        sender_ppk: &DiversifiedPublicKey,
+        receiver_ppk: &DiversifiedPublicKey,
+        phi: &PrfOutput,
+    ) {
+        self.append_u64(b"pid", pid as u64);
+        self.append_u64(b"sid", sid);
+        self.append_u64(b"nu", nu as u64);
+        self.append_g2(b"ppk_nu_1", &sender_ppk.ppk_1);
+        self.append_g2(b"ppk_nu_2", &sender_ppk.ppk_2);
+        self.append_g2(b"ppk_nu+1_1", &receiver_ppk.ppk_1);
+        self.append_g2(b"ppk_nu+1_2", &receiver_ppk.ppk_2);
+        self.append_g1(b"phi_nu+1", &phi.phi);
+    }
+}
+
+/// Transcript over the Poseidon duplex sponge
+#[derive(Clone, Debug)]
+pub struct PoseidonTranscript<'a> {
+    sponge: PoseidonSponge<'a>,
+}
+
+impl<'a> PoseidonTranscript<'a> {
+    /// Start a transcript for the given protocol label
+    pub fn new(hasher: &'a PoseidonHash, protocol: &'static [u8]) -> Self {
+        let mut sponge = hasher.sponge(DomainTag::Transcript);
+        sponge.absorb(&[label_to_field(protocol)]);
+        Self { sponge }
+    }
+}
+
+impl Transcript for PoseidonTranscript<'_> {
+    fn append_field_elements(&mut self, label: &'static [u8], elements: &[ScalarField]) {
+        self.sponge.absorb(&[
+            label_to_field(label),
+            ScalarField::from(elements.len() as u64),
+        ]);
+        self.sponge.absorb(elements);
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> ScalarField {
+        self.sponge.absorb(&[label_to_field(label)]);
+        let challenge = self.sponge.squeeze_one();
+        self.sponge.absorb(&[challenge]);
+        challenge
+    }
+}
+
+/// Transcript over SHA-256
+///
+/// Challenges are reduced from 512 bits of output, so they are statistically
+/// close to uniform in Fr.
+#[derive(Clone, Debug)]
+pub struct Sha256Transcript {
+    state: Sha256,
+}
+
+impl Sha256Transcript {
+    /// Start a transcript for the given protocol label
+    pub fn new(protocol: &'static [u8]) -> Self {
+        let mut transcript = Self {
+            state: Sha256::new(),
+        };
+        transcript.write_label(DomainTag::Transcript.label());
+        transcript.write_label(protocol);
+        transcript
+    }
+
+    fn write_label(&mut self, label: &[u8]) {
+        self.state.update((label.len() as u64).to_le_bytes());
+        self.state.update(label);
+    }
+}
+
+impl Transcript for Sha256Transcript {
+    fn append_field_elements(&mut self, label: &'static [u8], elements: &[ScalarField]) {
+        self.write_label(label);
+        self.state.update((elements.len() as u64).to_le_bytes());
+        for element in elements {
+            self.state.update(element.into_bigint().to_bytes_le());
+        }
+    }
+
+    fn challenge_scalar(&mut self, label: &'static [u8]) -> ScalarField {
+        self.write_label(label);
+
+        let mut bytes = Vec::with_capacity(64);
+        for counter in 0u8..2 {
+            let mut state = self.state.clone();
+            state.update([counter]);
+            bytes.extend_from_slice(&state.finalize());
+        }
+        self.state.update(&bytes);
+
+        ScalarField::from_le_bytes_mod_order(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve_ops::{diversify, keygen};
+    use ark_bls12_381::{G1Projective, G2Projective};
+    use ark_ec::CurveGroup;
+    use ark_std::UniformRand;
+    use rand::thread_rng;
+
+    /// Run the same sequence of appends on a transcript and return two challenges
+    fn run<T: Transcript>(transcript: &mut T, scalar: ScalarField, point: &G1Point) -> [ScalarField; 2] {
+        transcript.append_scalar(b"scalar", &scalar);
+        transcript.append_g1(b"point", point);
+        let first = transcript.challenge_scalar(b"c1");
+        let second = transcript.challenge_scalar(b"c2");
+        [first, second]
+    }
+
+    #[test]
+    fn test_transcripts_deterministic() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let scalar = ScalarField::rand(&mut rng);
+        let point = G1Projective::rand(&mut rng).into_affine();
+
+        let a = run(&mut PoseidonTranscript::new(&hasher, b"test"), scalar, &point);
+        let b = run(&mut PoseidonTranscript::new(&hasher, b"test"), scalar, &point);
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+
+        let a = run(&mut Sha256Transcript::new(b"test"), scalar, &point);
+        let b = run(&mut Sha256Transcript::new(b"test"), scalar, &point);
+        assert_eq!(a, b);
+        assert_ne!(a[0], a[1]);
+    }
+
+    #[test]
+    fn test_transcripts_bind_inputs() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let scalar = ScalarField::rand(&mut rng);
+        let point = G1Projective::rand(&mut rng).into_affine();
+        let other = ScalarField::rand(&mut rng);
+
+        let base = run(&mut PoseidonTranscript::new(&hasher, b"test"), scalar, &point);
+        assert_ne!(base, run(&mut PoseidonTranscript::new(&hasher, b"other"), scalar, &point));
+        assert_ne!(base, run(&mut PoseidonTranscript::new(&hasher, b"test"), other, &point));
+        assert_ne!(base, run(&mut PoseidonTranscript::new(&hasher, b"test"), scalar, &-point));
+
+        let base = run(&mut Sha256Transcript::new(b"test"), scalar, &point);
+        assert_ne!(base, run(&mut Sha256Transcript::new(b"other"), scalar, &point));
+        assert_ne!(base, run(&mut Sha256Transcript::new(b"test"), other, &point));
+        assert_ne!(base, run(&mut Sha256Transcript::new(b"test"), scalar, &-point));
+    }
+
+    #[test]
+    fn test_labels_are_bound() {
+        let hasher = PoseidonHash::new();
+        let value = ScalarField::from(1u64);
+
+        let mut a = PoseidonTranscript::new(&hasher, b"test");
+        a.append_scalar(b"x", &value);
+        let mut b = PoseidonTranscript::new(&hasher, b"test");
+        b.append_scalar(b"y", &value);
+        assert_ne!(a.challenge_scalar(b"c"), b.challenge_scalar(b"c"));
+
+        // Length prefixes keep [x, y] apart from [x] followed by [y]
+        let mut a = Sha256Transcript::new(b"test");
+        a.append_field_elements(b"v", &[value, value]);
+        let mut b = Sha256Transcript::new(b"test");
+        b.append_field_elements(b"v", &[value]);
+        b.append_field_elements(b"v", &[value]);
+        assert_ne!(a.challenge_scalar(b"c"), b.challenge_scalar(b"c"));
+    }
+
+    #[test]
+    fn test_hop_context() {
+        let mut rng = thread_rng();
+        let hasher = PoseidonHash::new();
+        let (_sk, pk) = keygen(&mut rng);
+        let (sender, _) = diversify(&pk, &mut rng);
+        let (receiver, _) = diversify(&pk, &mut rng);
+        let phi = PrfOutput {
+            phi: G1Projective::rand(&mut rng).into_affine(),
+        };
+
+        let challenge = |nu: usize, receiver: &DiversifiedPublicKey| {
+            let mut transcript = PoseidonTranscript::new(&hasher, b"hop");
+            transcript.append_hop_context(1, 100, nu, &sender, receiver, &phi);
+            transcript.challenge_scalar(b"c")
+        };
+
+        assert_eq!(challenge(3, &receiver), challenge(3, &receiver));
+        assert_ne!(challenge(3, &receiver), challenge(4, &receiver));
+        assert_ne!(challenge(3, &receiver), challenge(3, &sender));
+
+        // The hop proof and its key are bound as well
+        let g2 = G2Projective::rand(&mut rng).into_affine();
+        let challenge = |proof: &Groth16Proof<_>, vk: &VerifyingKey<_>| {
+            let mut transcript = Sha256Transcript::new(b"hop");
+            transcript.append_g2(b"g2", &g2);
+            transcript.append_groth16_proof(b"proof", proof);
+            transcript.append_groth16_vk(b"vk", vk);
+            transcript.challenge_scalar(b"c")
+        };
+        let proof = Groth16Proof::default();
+        let vk = VerifyingKey::default();
+        let base = challenge(&proof, &vk);
+        let mut other_proof = proof.clone();
+        other_proof.c = G1Projective::rand(&mut rng).into_affine();
+        assert_ne!(base, challenge(&other_proof, &vk));
+        let mut other_vk = vk.clone();
+        other_vk.delta_g2 = g2;
+        assert_ne!(base, challenge(&proof, &other_vk));
+    }
+}
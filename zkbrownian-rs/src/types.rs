@@ -147,8 +147,8 @@ pub struct Proof {
     pub pi_1: Vec<u8>, // G1 proof
     pub pi_2: Vec<u8>, // G1 proof (weights)
     pub pi_3: Vec<u8>, // G1 proof
-    pub pi_4_g1: Vec<u8>, // Schnorr in G1
-    pub pi_4_g2: Vec<u8>, // Schnorr in G2
+    pub pi_4_g1: Vec<u8>, // Bridging sigma proof in G1
+    pub pi_4_g2: Vec<u8>, // Public key operations sigma proof in G2
 }
 
 /// A single hop in the message history
@@ -198,6 +198,11 @@ impl Message {
     pub fn latest_ppk(&self) -> Option<&DiversifiedPublicKey> {
         self.hops.last().map(|h| &h.ppk)
     }
+
+    /// Diversified key of the current holder: ppk_ν, or ppk_0 before the first hop
+    pub fn current_ppk(&self) -> &DiversifiedPublicKey {
+        self.latest_ppk().unwrap_or(&self.ppk_0)
+    }
 }
 
 /// Weight entry for routing